@@ -0,0 +1,55 @@
+//! Times the tree walker against the bytecode VM on the same expressions.
+//! Run with `cargo bench`
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use math::{
+  bytecode::Bytecode, context::Context, expression::Expression, number::Number,
+};
+
+const EXPRESSIONS: [&str; 4] = [
+  "x^2 + 3x - 7",
+  "sin(x) * cos(y) + sqrt(x^2 + y^2)",
+  "|x - y| / (1 + e^-(x y)) + 10%",
+  "max(x, y, 2) * log(2, x + 1) + gamma(y + 1)",
+];
+
+/// A spread of inputs, so no single branch gets all the runs
+fn inputs() -> Vec<[Number; 2]> {
+  (0..64)
+    .map(|i| {
+      let x = Number::new(i as f64 / 64.0 + 0.5, 0.0);
+      [x, Number::new(2.0 - x.re, 0.0)]
+    })
+    .collect()
+}
+
+fn tree_vs_vm(c: &mut Criterion) {
+  let ctx = Context::default();
+  let inputs = inputs();
+  let mut group = c.benchmark_group("tree_vs_vm");
+  for input in EXPRESSIONS {
+    let expression = Expression::new(input).unwrap();
+    let bytecode = Bytecode::compile(&expression, &ctx).unwrap();
+    group.bench_with_input(BenchmarkId::new("tree", input), &inputs, |b, i| {
+      b.iter(|| {
+        for values in i {
+          black_box(expression.evaluate_with(&ctx, black_box(values)).ok());
+        }
+      })
+    });
+    let mut stack = vec![];
+    group.bench_with_input(BenchmarkId::new("vm", input), &inputs, |b, i| {
+      b.iter(|| {
+        for values in i {
+          black_box(bytecode.run_on(&mut stack, &ctx, black_box(values)).ok());
+        }
+      })
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, tree_vs_vm);
+criterion_main!(benches);
@@ -0,0 +1,525 @@
+//! Compiles expressions to a flat list of instructions for a small stack
+//! machine, which runs much faster than walking the tree when the same
+//! expression is evaluated over and over. Like [Expression], it only runs on
+//! the default complex backend
+
+use crate::{
+  context::Context,
+  error::{MathError, MathResult},
+  expression::Expression,
+  functions::Function,
+  lexer::Wrapping,
+  number::{sanitize_real, Number},
+  operator::{Operation, OP_ADD, OP_DIV, OP_EXP, OP_MULT, OP_SQRT, OP_SUB},
+  parse::{AstNode, Fixity},
+  span::Span,
+  visit::Visitor,
+};
+
+/// Indices point into the pools of the [Bytecode] holding the instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+  /// Pushes a value from the constant pool
+  Constant(u32),
+  /// Pushes the value bound to an input slot, or the variable of the same
+  /// name in the context when there's no value for it
+  Load(u32),
+  Prefix(u32),
+  Postfix(u32),
+  Binary(u32),
+  /// Binary operation whose right operand is relative to the left, as in
+  /// `200 + 10%`
+  Relative(u32),
+  /// Replaces the top of the stack with its magnitude
+  Abs,
+  /// Pops the arguments and pushes the result
+  Call {
+    function: u32,
+    args: u32,
+  },
+}
+
+#[derive(Debug, Clone)]
+pub struct Bytecode {
+  instructions: Vec<Instruction>,
+  /// Where each instruction came from, for errors
+  spans: Vec<Span>,
+  constants: Vec<Number>,
+  operations: Vec<Operation>,
+  functions: Vec<Function>,
+  inputs: Vec<String>,
+  max_stack: usize,
+}
+
+impl Bytecode {
+  /// Variables of `expression` become input slots, in the order of
+  /// [Expression::variables]. Constants like `pi` and every function are
+  /// looked up in `ctx` now rather than on each run
+  pub fn compile(
+    expression: &Expression,
+    ctx: &Context,
+  ) -> Result<Self, MathError> {
+    let mut compiler = Compiler {
+      ctx,
+      code: Bytecode {
+        instructions: vec![],
+        spans: vec![],
+        constants: vec![],
+        operations: vec![],
+        functions: vec![],
+        inputs: expression.variables().to_vec(),
+        max_stack: 0,
+      },
+      function_names: vec![],
+      depth: 0,
+    };
+    compiler.compile(&expression.ast().root)?;
+    Ok(compiler.code)
+  }
+
+  pub fn instructions(&self) -> &[Instruction] {
+    &self.instructions
+  }
+
+  /// Names of the input slots, which `values` line up with in
+  /// [Bytecode::run]
+  pub fn inputs(&self) -> &[String] {
+    &self.inputs
+  }
+
+  /// `ctx` is the scope of user defined functions, which should be the one
+  /// the code was compiled against. Inputs past the end of `values` are
+  /// read from it too, like [Expression::evaluate_with] does
+  pub fn run(&self, ctx: &Context, values: &[Number]) -> MathResult {
+    let mut stack = Vec::with_capacity(self.max_stack);
+    self.run_on(&mut stack, ctx, values)
+  }
+
+  /// Like [Bytecode::run], reusing `stack` to save allocating one each time
+  pub fn run_on(
+    &self,
+    stack: &mut Vec<Number>,
+    ctx: &Context,
+    values: &[Number],
+  ) -> MathResult {
+    stack.clear();
+    for (index, instruction) in self.instructions.iter().enumerate() {
+      self
+        .step(*instruction, stack, ctx, values)
+        .map_err(|err| err.or_span(self.spans[index]))?;
+    }
+    stack.pop().ok_or(MathError::NoInput(Span::default()))
+  }
+
+  fn step(
+    &self,
+    instruction: Instruction,
+    stack: &mut Vec<Number>,
+    ctx: &Context,
+    values: &[Number],
+  ) -> Result<(), MathError> {
+    let value = match instruction {
+      Instruction::Constant(index) => self.constants[index as usize],
+      Instruction::Load(slot) => match values.get(slot as usize) {
+        Some(value) => *value,
+        None => ctx.read_variable(self.inputs[slot as usize].clone())?,
+      },
+      Instruction::Prefix(op) => {
+        let operand = stack.pop().unwrap();
+        self.operations[op as usize].perform_unary(operand)?
+      },
+      Instruction::Postfix(op) => {
+        let operand = stack.pop().unwrap();
+        self.operations[op as usize].perform_postfix(operand)?
+      },
+      Instruction::Binary(op) => {
+        let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+        self.operations[op as usize].perform_binary(a, b)?
+      },
+      Instruction::Relative(op) => {
+        let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+        self.operations[op as usize].perform_relative(a, b)?
+      },
+      Instruction::Abs => stack.pop().unwrap().norm().into(),
+      Instruction::Call { function, args } => {
+        let start = stack.len() - args as usize;
+        let result =
+          self.functions[function as usize].compute(&stack[start..], ctx, 1)?;
+        stack.truncate(start);
+        result
+      },
+    };
+    stack.push(value);
+    Ok(())
+  }
+
+  /// Batch evaluation, where `columns[i]` holds every value of input `i`
+  /// and row `n` of `out` is computed from row `n` of each column. Errors
+  /// are reported per row, one bad row doesn't stop the rest
+  ///
+  /// # Panics
+  ///
+  /// If there are fewer columns than inputs, or a column is shorter than
+  /// `out`
+  pub fn run_batch(
+    &self,
+    ctx: &Context,
+    columns: &[&[Number]],
+    out: &mut [MathResult],
+  ) {
+    self.check_columns(columns.iter().map(|c| c.len()), out.len());
+    let mut stack = Vec::with_capacity(self.max_stack);
+    let mut row = vec![Number::default(); self.inputs.len()];
+    for (n, result) in out.iter_mut().enumerate() {
+      for (value, column) in row.iter_mut().zip(columns) {
+        *value = column[n];
+      }
+      *result = self.run_on(&mut stack, ctx, &row);
+    }
+  }
+
+  /// Like [Bytecode::run_batch] with real valued inputs and outputs.
+  /// Arithmetic runs on plain `f64`s, dropping back to complex numbers only
+  /// for rows that leave the real line along the way. Rows whose result
+  /// isn't real are a [MathError::ComplexResult]
+  pub fn run_batch_real(
+    &self,
+    ctx: &Context,
+    columns: &[&[f64]],
+    out: &mut [Result<f64, MathError>],
+  ) {
+    self.check_columns(columns.iter().map(|c| c.len()), out.len());
+    let kinds: Vec<_> = self.operations.iter().map(RealOp::of).collect();
+    let mut stack = Vec::with_capacity(self.max_stack);
+    let mut complex_stack = Vec::with_capacity(self.max_stack);
+    let mut args = vec![];
+    let mut row = vec![0.0; self.inputs.len()];
+    let mut complex_row = vec![Number::default(); self.inputs.len()];
+    for (n, result) in out.iter_mut().enumerate() {
+      for (value, column) in row.iter_mut().zip(columns) {
+        *value = column[n];
+      }
+      *result = match self.run_real(&kinds, &mut stack, &mut args, ctx, &row) {
+        Ok(value) => Ok(value),
+        Err(Stop::Error(err)) => Err(err),
+        Err(Stop::Complex) => {
+          for (complex, real) in complex_row.iter_mut().zip(&row) {
+            *complex = Number::new(*real, 0.0);
+          }
+          match self.run_on(&mut complex_stack, ctx, &complex_row) {
+            // Going around the complex plane leaves rounding
+            // error behind, as in `sqrt(x)^2`. It's only ever a few
+            // ulps of the result, so anything bigger is really there
+            Ok(value) if value.im.abs() <= ROUNDING * value.norm() => {
+              Ok(value.re)
+            },
+            Ok(_) => Err(MathError::ComplexResult(
+              self.spans.last().copied().unwrap_or_default(),
+            )),
+            Err(err) => Err(err),
+          }
+        },
+      };
+    }
+  }
+
+  fn check_columns(
+    &self,
+    lengths: impl ExactSizeIterator<Item = usize>,
+    rows: usize,
+  ) {
+    assert!(
+      lengths.len() >= self.inputs.len(),
+      "expected a column for each of {} inputs, got {}",
+      self.inputs.len(),
+      lengths.len()
+    );
+    for (index, length) in lengths.take(self.inputs.len()).enumerate() {
+      assert!(
+        length >= rows,
+        "column {index} has {length} rows, expected {rows}"
+      );
+    }
+  }
+
+  fn run_real(
+    &self,
+    kinds: &[RealOp],
+    stack: &mut Vec<f64>,
+    args: &mut Vec<Number>,
+    ctx: &Context,
+    values: &[f64],
+  ) -> Result<f64, Stop> {
+    stack.clear();
+    for (index, instruction) in self.instructions.iter().enumerate() {
+      self
+        .step_real(*instruction, kinds, stack, args, ctx, values)
+        .map_err(|stop| match stop {
+          Stop::Error(err) => Stop::Error(err.or_span(self.spans[index])),
+          Stop::Complex => Stop::Complex,
+        })?;
+    }
+    stack
+      .pop()
+      .ok_or(Stop::Error(MathError::NoInput(Span::default())))
+  }
+
+  fn step_real(
+    &self,
+    instruction: Instruction,
+    kinds: &[RealOp],
+    stack: &mut Vec<f64>,
+    args: &mut Vec<Number>,
+    ctx: &Context,
+    values: &[f64],
+  ) -> Result<(), Stop> {
+    let value = match instruction {
+      Instruction::Constant(index) => real(self.constants[index as usize])?,
+      Instruction::Load(slot) => values[slot as usize],
+      Instruction::Prefix(op) => {
+        let a = stack.pop().unwrap();
+        match kinds[op as usize] {
+          RealOp::Sub => -a,
+          RealOp::Sqrt if a >= 0.0 => sanitize_real(a.sqrt())?,
+          _ => real(self.operations[op as usize].perform_unary(a.into())?)?,
+        }
+      },
+      Instruction::Postfix(op) => {
+        let a = stack.pop().unwrap();
+        real(self.operations[op as usize].perform_postfix(a.into())?)?
+      },
+      Instruction::Binary(op) => {
+        let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+        match kinds[op as usize] {
+          RealOp::Add => sanitize_real(a + b)?,
+          RealOp::Sub => sanitize_real(a - b)?,
+          RealOp::Mul => sanitize_real(a * b)?,
+          RealOp::Div if b == 0.0 => {
+            return Err(Stop::Error(MathError::DivisionByZero(Span::default())))
+          },
+          RealOp::Div => sanitize_real(a / b)?,
+          RealOp::Pow if a >= 0.0 || b.fract() == 0.0 => {
+            sanitize_real(a.powf(b))?
+          },
+          _ => real(
+            self.operations[op as usize].perform_binary(a.into(), b.into())?,
+          )?,
+        }
+      },
+      Instruction::Relative(op) => {
+        let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+        real(
+          self.operations[op as usize].perform_relative(a.into(), b.into())?,
+        )?
+      },
+      Instruction::Abs => stack.pop().unwrap().abs(),
+      Instruction::Call {
+        function,
+        args: count,
+      } => {
+        let start = stack.len() - count as usize;
+        args.clear();
+        args.extend(stack.drain(start..).map(Number::from));
+        real(self.functions[function as usize].compute(args, ctx, 1)?)?
+      },
+    };
+    stack.push(value);
+    Ok(())
+  }
+}
+
+/// Operations [Bytecode::run_batch_real] can do without complex numbers
+#[derive(Debug, Clone, Copy)]
+enum RealOp {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Pow,
+  Sqrt,
+  Other,
+}
+
+impl RealOp {
+  fn of(op: &Operation) -> Self {
+    match op {
+      op if *op == OP_ADD => RealOp::Add,
+      op if *op == OP_SUB => RealOp::Sub,
+      op if *op == OP_MULT => RealOp::Mul,
+      op if *op == OP_DIV => RealOp::Div,
+      op if *op == OP_EXP => RealOp::Pow,
+      op if *op == OP_SQRT => RealOp::Sqrt,
+      _ => RealOp::Other,
+    }
+  }
+}
+
+/// Largest imaginary part, relative to the size of the result, that a real
+/// valued batch run puts down to rounding
+const ROUNDING: f64 = 64.0 * f64::EPSILON;
+
+/// Why a real valued run ended early
+enum Stop {
+  Error(MathError),
+  /// Some value along the way wasn't real
+  Complex,
+}
+
+impl From<MathError> for Stop {
+  fn from(err: MathError) -> Self {
+    Stop::Error(err)
+  }
+}
+
+fn real(num: Number) -> Result<f64, Stop> {
+  match num.im == 0.0 {
+    true => Ok(num.re),
+    false => Err(Stop::Complex),
+  }
+}
+
+struct Compiler<'c, 'p> {
+  ctx: &'c Context<'p>,
+  code: Bytecode,
+  function_names: Vec<String>,
+  /// Values on the stack at this point of the program
+  depth: usize,
+}
+
+impl Compiler<'_, '_> {
+  fn compile(&mut self, node: &AstNode) -> Result<(), MathError> {
+    node.accept(self).map_err(|err| err.or_span(node.span()))
+  }
+
+  /// `popped` values are replaced with the one the instruction pushes
+  fn emit(&mut self, instruction: Instruction, popped: usize, span: Span) {
+    self.code.instructions.push(instruction);
+    self.code.spans.push(span);
+    self.depth = self.depth - popped + 1;
+    self.code.max_stack = self.code.max_stack.max(self.depth);
+  }
+
+  fn constant(&mut self, value: Number, span: Span) {
+    let index = match self.code.constants.iter().position(|c| *c == value) {
+      Some(index) => index,
+      None => {
+        self.code.constants.push(value);
+        self.code.constants.len() - 1
+      },
+    };
+    self.emit(Instruction::Constant(index as u32), 0, span);
+  }
+
+  fn operation(&mut self, op: &Operation) -> u32 {
+    let index = match self.code.operations.iter().position(|o| o == op) {
+      Some(index) => index,
+      None => {
+        self.code.operations.push(op.clone());
+        self.code.operations.len() - 1
+      },
+    };
+    index as u32
+  }
+}
+
+impl Visitor for Compiler<'_, '_> {
+  type Output = Result<(), MathError>;
+
+  fn visit_constant(&mut self, value: Number, span: Span) -> Self::Output {
+    self.constant(value, span);
+    Ok(())
+  }
+
+  fn visit_variable(&mut self, name: &str, span: Span) -> Self::Output {
+    match self.code.inputs.iter().position(|input| input == name) {
+      Some(slot) => self.emit(Instruction::Load(slot as u32), 0, span),
+      None => self.constant(self.ctx.read_variable(name.to_string())?, span),
+    }
+    Ok(())
+  }
+
+  fn visit_call(
+    &mut self,
+    name: &str,
+    args: &[AstNode],
+    span: Span,
+  ) -> Self::Output {
+    let function = match self.function_names.iter().position(|n| n == name) {
+      Some(index) => index,
+      None => {
+        let function = self.ctx.function(name).ok_or_else(|| {
+          MathError::UndefinedFunction(name.to_string(), span)
+        })?;
+        self.code.functions.push(function.clone());
+        self.function_names.push(name.to_string());
+        self.code.functions.len() - 1
+      },
+    };
+    if !self.code.functions[function].arity().accepts(args.len()) {
+      let arity = self.code.functions[function].arity();
+      return Err(MathError::ArgumentCount(
+        name.to_string(),
+        arity,
+        args.len(),
+        span,
+      ));
+    }
+    for arg in args {
+      self.compile(arg)?;
+    }
+    let instruction = Instruction::Call {
+      function: function as u32,
+      args: args.len() as u32,
+    };
+    self.emit(instruction, args.len(), span);
+    Ok(())
+  }
+
+  fn visit_unary(
+    &mut self,
+    op: &Operation,
+    fixity: Fixity,
+    operand: &AstNode,
+    span: Span,
+  ) -> Self::Output {
+    self.compile(operand)?;
+    let op = self.operation(op);
+    let instruction = match fixity {
+      Fixity::Prefix => Instruction::Prefix(op),
+      Fixity::Postfix => Instruction::Postfix(op),
+    };
+    self.emit(instruction, 1, span);
+    Ok(())
+  }
+
+  fn visit_binary(
+    &mut self,
+    op: &Operation,
+    left: &AstNode,
+    right: &AstNode,
+    span: Span,
+  ) -> Self::Output {
+    self.compile(left)?;
+    self.compile(right)?;
+    let index = self.operation(op);
+    let instruction = match right.is_relative() {
+      true => Instruction::Relative(index),
+      false => Instruction::Binary(index),
+    };
+    self.emit(instruction, 2, span);
+    Ok(())
+  }
+
+  fn visit_group(
+    &mut self,
+    wrapping: Wrapping,
+    inner: &AstNode,
+    span: Span,
+  ) -> Self::Output {
+    self.compile(inner)?;
+    if wrapping == Wrapping::Bar {
+      self.emit(Instruction::Abs, 1, span);
+    }
+    Ok(())
+  }
+}
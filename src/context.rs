@@ -3,63 +3,175 @@ use std::collections::HashMap;
 use crate::{
   error::{MathError, MathResult},
   functions::Function,
-  number::Number,
+  number::{Number, Numeric},
+  span::Span,
   variables::Variable,
 };
 
+/// How many scopes deep function calls may nest before giving up, since
+/// nothing stops a user function from calling itself forever
+const MAX_DEPTH: usize = 256;
+
+/// Variables and functions visible to an expression. Lookups that miss fall
+/// through to the parent scope, if there is one
 #[derive(Debug, Clone)]
-pub struct Context {
-  vars: HashMap<String, Variable>,
-  funcs: HashMap<String, Function>,
+pub struct Context<'a, N: Numeric = Number> {
+  vars: HashMap<String, Variable<N>>,
+  funcs: HashMap<String, Function<N>>,
+  /// How custom functions are written in LaTeX
+  latex_names: HashMap<String, String>,
+  /// Presentation and content MathML for custom function names
+  mathml_names: HashMap<String, (String, String)>,
+  parent: Option<&'a Context<'a, N>>,
+  depth: usize,
 }
 
-impl Context {
+impl Context<'_> {
+  /// An empty scope for the default backend, see [Context::empty] for others
   pub fn new() -> Self {
+    Self::empty()
+  }
+}
+
+impl<'a, N: Numeric> Context<'a, N> {
+  /// An empty scope, without even the built in constants
+  pub fn empty() -> Self {
     Self {
       vars: HashMap::new(),
       funcs: HashMap::new(),
+      latex_names: HashMap::new(),
+      mathml_names: HashMap::new(),
+      parent: None,
+      depth: 0,
     }
   }
 
   pub fn with(
-    variables: HashMap<String, Variable>,
-    functions: HashMap<String, Function>,
+    variables: HashMap<String, Variable<N>>,
+    functions: HashMap<String, Function<N>>,
   ) -> Self {
     Self {
       vars: variables,
       funcs: functions,
+      latex_names: HashMap::new(),
+      mathml_names: HashMap::new(),
+      parent: None,
+      depth: 0,
     }
   }
 
-  pub fn assign_variable(&mut self, name: String, value: Number) {
+  /// The built in constants and functions for any backend, as in
+  /// `Context::<f64>::with_builtins()`. [Context::default] is this for the
+  /// default backend
+  pub fn with_builtins() -> Self {
+    Self::with(Variable::builtins(), Function::builtins())
+  }
+
+  /// Empty scope on top of `parent`, whose names it shadows. `depth` counts
+  /// the function calls leading up to it
+  pub fn child(parent: &'a Context<'a, N>, depth: usize) -> Self {
+    Self {
+      vars: HashMap::new(),
+      funcs: HashMap::new(),
+      latex_names: HashMap::new(),
+      mathml_names: HashMap::new(),
+      parent: Some(parent),
+      depth,
+    }
+  }
+
+  pub fn assign_variable(&mut self, name: String, value: N) {
     self.vars.insert(name, Variable(value));
   }
 
-  pub fn read_variable(&self, name: String) -> MathResult {
-    match self.vars.get(&name) {
-      Some(var) => Ok(var.0.clone()),
-      None => Err(MathError::UndefinedVariable(name)),
+  pub fn read_variable(&self, name: String) -> MathResult<N> {
+    match (self.vars.get(&name), self.parent) {
+      (Some(var), _) => Ok(var.0.clone()),
+      (None, Some(parent)) => parent.read_variable(name),
+      (None, None) => Err(MathError::UndefinedVariable(name, Span::default())),
     }
   }
 
-  pub fn assign_function(&mut self, name: String, function: Function) {
+  pub fn assign_function(&mut self, name: String, function: Function<N>) {
     self.funcs.insert(name, function);
   }
 
+  /// Has `name` written as `latex` when exporting, as in `\operatorname{erf}`
+  pub fn assign_latex_name(&mut self, name: String, latex: String) {
+    self.latex_names.insert(name, latex);
+  }
+
+  pub fn latex_name(&self, name: &str) -> Option<&str> {
+    match (self.latex_names.get(name), self.parent) {
+      (Some(latex), _) => Some(latex),
+      (None, Some(parent)) => parent.latex_name(name),
+      (None, None) => None,
+    }
+  }
+
+  /// Has `name` written as the `presentation` markup, like `<mi>erf</mi>`,
+  /// and applied as the `content` markup, like `<csymbol>erf</csymbol>`
+  pub fn assign_mathml_name(
+    &mut self,
+    name: String,
+    presentation: String,
+    content: String,
+  ) {
+    self.mathml_names.insert(name, (presentation, content));
+  }
+
+  pub fn mathml_name(&self, name: &str) -> Option<(&str, &str)> {
+    match (self.mathml_names.get(name), self.parent) {
+      (Some((presentation, content)), _) => Some((presentation, content)),
+      (None, Some(parent)) => parent.mathml_name(name),
+      (None, None) => None,
+    }
+  }
+
+  /// The function `name` refers to in this scope
+  pub fn function(&self, name: &str) -> Option<&Function<N>> {
+    self.find_function(name).map(|(func, _)| func)
+  }
+
+  /// Finds `name` along with the scope it was defined in
+  fn find_function(
+    &self,
+    name: &str,
+  ) -> Option<(&Function<N>, &Context<'a, N>)> {
+    match (self.funcs.get(name), self.parent) {
+      (Some(func), _) => Some((func, self)),
+      (None, Some(parent)) => parent.find_function(name),
+      (None, None) => None,
+    }
+  }
+
+  /// Functions run in a scope on top of the one they were defined in
   pub fn compute_function(
     &self,
     name: String,
-    operand: MathResult,
-  ) -> MathResult {
-    match self.funcs.get(&name) {
-      Some(func) => func.compute(operand),
-      None => Err(MathError::UndefinedFunction(name)),
+    operands: &[N],
+  ) -> MathResult<N> {
+    let (func, scope) = match self.find_function(&name) {
+      Some(found) => found,
+      None => return Err(MathError::UndefinedFunction(name, Span::default())),
+    };
+    if !func.arity().accepts(operands.len()) {
+      return Err(MathError::ArgumentCount(
+        name,
+        func.arity(),
+        operands.len(),
+        Span::default(),
+      ));
+    }
+    if self.depth >= MAX_DEPTH {
+      return Err(MathError::TooDeep(Span::default()));
     }
+    func.compute(operands, scope, self.depth + 1)
   }
 }
 
-impl Default for Context {
+impl Default for Context<'_> {
   fn default() -> Self {
-    Self::with(Variable::default_set(), Function::default_set())
+    Self::with_builtins()
   }
 }
@@ -0,0 +1,599 @@
+//! Fixed point decimal arithmetic to any number of digits, for checking
+//! answers past the 16 or so digits floating point gets right. Pick the
+//! digits after the decimal point with the type, as in
+//! `evaluate_as::<Decimal<50>>(input)`. Being part of the type, the digits
+//! are fixed when compiling rather than chosen at runtime. They count places
+//! rather than significant digits, so a tiny result like `e^-100` only keeps
+//! the few of its digits that land within them.
+//!
+//! The four operations, powers, `exp`, `sqrt`, `ln` and the trig functions
+//! are right to every place for the operands they're given. Constants like
+//! `pi` are worked out again to as many places as an operation on them needs,
+//! but other operands are rounded, results along the way included, so
+//! something that scales their rounding error up, like `(pi + 1)^100`, has
+//! fewer places right. Anything else, like `gamma`, goes through [Number] and
+//! is only as precise as floating point
+
+use std::{
+  cmp::Ordering,
+  f64::consts::{LN_10, LN_2, LOG10_2},
+  ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use num::{BigInt, BigRational, FromPrimitive, One, Signed, ToPrimitive, Zero};
+
+use crate::{
+  error::{MathError, MathResult},
+  number::{factorial, Number, Numeric},
+  rational::{parse_exact, MAX_BITS},
+  span::Span,
+};
+
+/// Extra digits carried along to absorb rounding error
+const GUARD: u32 = 10;
+
+/// Value times `10^(DIGITS + GUARD)`, rounded to an integer
+#[derive(Debug, Clone)]
+pub struct Decimal<const DIGITS: u32> {
+  scaled: BigInt,
+  /// Set on constants like `pi`, which are worked out again to as many
+  /// digits as an operation on them needs, so `pi^100` is right to every
+  /// place
+  constant: Option<Constant>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Constant {
+  Pi,
+  Tau,
+  E,
+}
+
+impl Constant {
+  fn scaled(self, unit: &BigInt) -> BigInt {
+    match self {
+      Constant::Pi => pi(unit),
+      Constant::Tau => pi(unit) * 2,
+      Constant::E => exp(unit, unit),
+    }
+  }
+}
+
+fn pow10(n: u32) -> BigInt {
+  BigInt::from(10).pow(n)
+}
+
+/// `n / d`, rounding halves away from zero
+fn div_round(n: BigInt, d: &BigInt) -> BigInt {
+  let (q, r) = (&n / d, &n % d);
+  if r.abs() * 2 < d.abs() {
+    return q;
+  }
+  match n.is_negative() == d.is_negative() {
+    true => q + 1,
+    false => q - 1,
+  }
+}
+
+// Fixed point helpers, where `unit` is the scaled value of one
+
+fn mul(a: &BigInt, b: &BigInt, unit: &BigInt) -> BigInt {
+  div_round(a * b, unit)
+}
+
+fn div(a: &BigInt, b: &BigInt, unit: &BigInt) -> BigInt {
+  div_round(a * unit, b)
+}
+
+fn sqrt(x: &BigInt, unit: &BigInt) -> BigInt {
+  (x * unit).sqrt()
+}
+
+/// Sum of `x^n / n` over odd `n`, alternating in sign if `alternate` is set.
+/// This is `atan` or `atanh`, converging quickly for small `x`
+fn odd_series(x: &BigInt, unit: &BigInt, alternate: bool) -> BigInt {
+  let square = mul(x, x, unit);
+  let (mut power, mut sum, mut n) = (x.clone(), x.clone(), 1u32);
+  loop {
+    power = mul(&power, &square, unit);
+    n += 2;
+    let term = &power / n;
+    if term.is_zero() {
+      return sum;
+    }
+    match alternate && n % 4 == 3 {
+      true => sum -= term,
+      false => sum += term,
+    }
+  }
+}
+
+fn pi(unit: &BigInt) -> BigInt {
+  // Machin's formula
+  let fifth = div_round(unit.clone(), &BigInt::from(5));
+  let small = div_round(unit.clone(), &BigInt::from(239));
+  odd_series(&fifth, unit, true) * 16 - odd_series(&small, unit, true) * 4
+}
+
+fn exp(x: &BigInt, unit: &BigInt) -> BigInt {
+  if x.is_negative() {
+    // Too small to show up at all, which saves working out a huge `e^-x`
+    if -x > unit * unit.bits() {
+      return BigInt::zero();
+    }
+    return div(unit, &exp(&-x, unit), unit);
+  }
+  // Halve into range, then square back up
+  let mut halvings = 0;
+  let mut x = x.clone();
+  while x > *unit {
+    x = div_round(x, &BigInt::from(2));
+    halvings += 1;
+  }
+  let (mut term, mut sum, mut n) = (unit.clone(), unit.clone(), 0u32);
+  loop {
+    n += 1;
+    term = div_round(&term * &x, &(unit * n));
+    if term.is_zero() {
+      break;
+    }
+    sum += &term;
+  }
+  for _ in 0..halvings {
+    sum = mul(&sum, &sum, unit);
+  }
+  sum
+}
+
+/// Natural log of a positive `x`
+fn ln(x: &BigInt, unit: &BigInt) -> BigInt {
+  // `x = m * 2^shift`, with `m` between one and two
+  let mut shift = x.bits() as i64 - unit.bits() as i64;
+  let mut m = match shift >= 0 {
+    true => x >> shift as usize,
+    false => x << -shift as usize,
+  };
+  while m >= unit * 2 {
+    m = div_round(m, &BigInt::from(2));
+    shift += 1;
+  }
+  while m < *unit {
+    m *= 2;
+    shift -= 1;
+  }
+  let atanh = |z: &BigInt| odd_series(z, unit, false) * 2;
+  let ln2 = atanh(&div_round(unit.clone(), &BigInt::from(3)));
+  atanh(&div(&(&m - unit), &(&m + unit), unit)) + ln2 * shift
+}
+
+fn atan(x: &BigInt, unit: &BigInt) -> BigInt {
+  if x.is_negative() {
+    return -atan(&-x, unit);
+  }
+  if x > unit {
+    return pi(unit) / 2 - atan(&div(unit, x, unit), unit);
+  }
+  // Each step halves the angle, so the series converges faster
+  let mut x = x.clone();
+  for _ in 0..3 {
+    let hypotenuse = sqrt(&(unit + mul(&x, &x, unit)), unit);
+    x = div(&x, &(unit + hypotenuse), unit);
+  }
+  odd_series(&x, unit, true) * 8
+}
+
+fn sin_cos(x: &BigInt, unit: &BigInt) -> (BigInt, BigInt) {
+  let turn = pi(unit) * 2;
+  let x = x - div_round(x.clone(), &turn) * &turn;
+  let square = mul(&x, &x, unit);
+  let series = |first: BigInt, mut n: u32| {
+    let (mut term, mut sum) = (first.clone(), first);
+    loop {
+      term = -div_round(&term * &square, &(unit * ((n + 1) * (n + 2))));
+      if term.is_zero() {
+        return sum;
+      }
+      sum += &term;
+      n += 2;
+    }
+  };
+  (series(x.clone(), 1), series(unit.clone(), 0))
+}
+
+fn complex<T>() -> MathResult<T> {
+  Err(MathError::ComplexResult(Span::default()))
+}
+
+/// Digits before the point of `e^power`, all of which have to be right on
+/// top of the usual places. Past the limit on integer powers it's too big
+fn exp_digits(power: f64) -> MathResult<u32> {
+  let digits = power.max(0.0) / LN_10;
+  match digits > MAX_BITS as f64 * LOG10_2 {
+    true => Err(MathError::TooBig(Span::default())),
+    false => Ok(digits.ceil() as u32),
+  }
+}
+
+impl<const DIGITS: u32> Decimal<DIGITS> {
+  const SCALE: u32 = DIGITS + GUARD;
+
+  fn from_scaled(scaled: BigInt) -> Self {
+    Self {
+      scaled,
+      constant: None,
+    }
+  }
+
+  fn unit() -> BigInt {
+    pow10(Self::SCALE)
+  }
+
+  fn from_rational(value: &BigRational) -> Self {
+    Self::from_scaled(div_round(value.numer() * Self::unit(), value.denom()))
+  }
+
+  fn is_integer(&self) -> bool {
+    (&self.scaled % Self::unit()).is_zero()
+  }
+
+  /// Runs `func` with `extra` more digits than usual
+  fn with_precision(
+    &self,
+    extra: u32,
+    func: impl FnOnce(&BigInt, &BigInt) -> BigInt,
+  ) -> Self {
+    let more = pow10(extra);
+    let unit = Self::unit() * &more;
+    let x = match self.constant {
+      Some(constant) => constant.scaled(&unit),
+      None => &self.scaled * &more,
+    };
+    Self::from_scaled(div_round(func(&x, &unit), &more))
+  }
+
+  /// Digits before the decimal point, which trig functions have to get
+  /// rid of before they can start
+  fn magnitude(&self) -> u32 {
+    (&self.scaled / Self::unit()).to_string().len() as u32
+  }
+
+  /// Base two log of the size, near enough to count digits with. Unlike
+  /// going through [Number], it doesn't overflow
+  fn log2(&self) -> f64 {
+    let shift = self.scaled.bits().saturating_sub(64);
+    let top = (self.scaled.abs() >> shift).to_f64().unwrap_or(0.0);
+    top.log2() + shift as f64 - Self::SCALE as f64 / LOG10_2
+  }
+}
+
+impl<const DIGITS: u32> Numeric for Decimal<DIGITS> {
+  /// Imaginary literals like `2i` aren't numbers here
+  fn parse_literal(literal: &str) -> Option<Self> {
+    parse_exact(literal).map(|value| Self::from_rational(&value))
+  }
+
+  fn to_number(&self) -> Number {
+    let value = BigRational::new(self.scaled.clone(), Self::unit());
+    Number::new(value.to_f64().unwrap_or(f64::NAN), 0.0)
+  }
+
+  fn from_number(num: Number) -> MathResult<Self> {
+    if num.im != 0.0 {
+      return complex();
+    }
+    match BigRational::from_float(num.re) {
+      Some(value) => Ok(Self::from_rational(&value)),
+      None => Err(MathError::Undefined(Span::default())),
+    }
+  }
+
+  /// Fixed point values are always numbers
+  fn sanitize(self) -> MathResult<Self> {
+    Ok(self)
+  }
+
+  fn constant(name: &str) -> Option<Self> {
+    let constant = match name {
+      "pi" => Constant::Pi,
+      "tau" => Constant::Tau,
+      "e" => Constant::E,
+      _ => return None,
+    };
+    let mut value =
+      Self::one().with_precision(GUARD, |_, unit| constant.scaled(unit));
+    value.constant = Some(constant);
+    Some(value)
+  }
+
+  fn real_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.scaled.cmp(&other.scaled))
+  }
+
+  fn abs(self) -> Self {
+    Self::from_scaled(self.scaled.abs())
+  }
+
+  fn pow(self, exponent: Self) -> MathResult<Self> {
+    // `exp` skips the huge powers in between, as in `e^-(10^10)`
+    if self.constant == Some(Constant::E) {
+      return exponent.exp();
+    }
+    let unit = Self::unit();
+    if exponent.is_integer() {
+      let n = &exponent.scaled / &unit;
+      if self.is_zero() {
+        return match n.sign() {
+          num::bigint::Sign::Minus => {
+            Err(MathError::DivisionByZero(Span::default()))
+          },
+          num::bigint::Sign::NoSign => Ok(Self::one()),
+          num::bigint::Sign::Plus => Ok(self),
+        };
+      }
+      let Some(magnitude) = n.abs().to_u64() else {
+        return Err(MathError::TooBig(Span::default()));
+      };
+      // Bits the result has before the point, as in `2^100` or `0.1^-100`
+      let growth = match (n.is_negative(), self.scaled.abs() > unit) {
+        (false, true) | (true, false) => self.log2().abs() * magnitude as f64,
+        _ => 0.0,
+      };
+      if growth > MAX_BITS as f64 {
+        return Err(MathError::TooBig(Span::default()));
+      }
+      // Every one of those digits has to be right too, unless the power of
+      // an integer is exact anyway. A negative power is worked out from a
+      // tiny positive one, which needs as many again to keep its own
+      let digits = (growth * LOG10_2).ceil() as u32 + 1;
+      let extra = match (n.is_negative(), self.is_integer()) {
+        (false, true) => 0,
+        (false, false) => digits,
+        (true, _) => digits * 2,
+      };
+      let power = self.with_precision(GUARD + extra, |x, unit| {
+        let (mut result, mut base, mut left) =
+          (unit.clone(), x.clone(), magnitude);
+        while left > 0 {
+          if left % 2 == 1 {
+            result = mul(&result, &base, unit);
+          }
+          base = mul(&base, &base, unit);
+          left /= 2;
+        }
+        match n.is_negative() {
+          true => div(unit, &result, unit),
+          false => result,
+        }
+      });
+      return Ok(power);
+    }
+    match self.scaled.sign() {
+      num::bigint::Sign::Minus => complex(),
+      num::bigint::Sign::NoSign if exponent.scaled.is_negative() => {
+        Err(MathError::DivisionByZero(Span::default()))
+      },
+      num::bigint::Sign::NoSign => Ok(self),
+      num::bigint::Sign::Plus => {
+        // `exponent` scales up the error in `ln(self)`, which `exp` then
+        // makes relative to the result, so both need more digits
+        let power = self.log2() * LN_2 * exponent.to_number().re;
+        let extra = exp_digits(power)? + exponent.magnitude();
+        Ok(self.with_precision(GUARD + extra, |x, unit| {
+          let exponent = &exponent.scaled * (unit / Self::unit());
+          exp(&mul(&ln(x, unit), &exponent, unit), unit)
+        }))
+      },
+    }
+  }
+
+  fn sqrt(self) -> MathResult<Self> {
+    match self.scaled.is_negative() {
+      true => complex(),
+      false => Ok(self.with_precision(GUARD, sqrt)),
+    }
+  }
+
+  fn factorial(self) -> MathResult<Self> {
+    if !self.is_integer() || self.scaled.is_negative() {
+      return Self::from_number(factorial(self.to_number())?);
+    }
+    let n = (&self.scaled / Self::unit())
+      .to_u64()
+      .filter(|n| *n < 1 << 16);
+    let Some(n) = n else {
+      return Err(MathError::TooBig(Span::default()));
+    };
+    let product = (1..=n).fold(BigInt::one(), |acc, k| acc * k);
+    Ok(Self::from_scaled(product * Self::unit()))
+  }
+
+  fn exp(self) -> MathResult<Self> {
+    let digits = exp_digits(self.to_number().re)?;
+    Ok(self.with_precision(GUARD + digits, exp))
+  }
+
+  fn ln(self) -> MathResult<Self> {
+    match self.scaled.sign() {
+      num::bigint::Sign::Minus => complex(),
+      num::bigint::Sign::NoSign => Err(MathError::TooBig(Span::default())),
+      num::bigint::Sign::Plus => {
+        // `ln 2` is multiplied up by the power of two taken out of the value
+        let digits = (self.log2().abs() + 1.0).log10().ceil() as u32;
+        Ok(self.with_precision(GUARD + digits, ln))
+      },
+    }
+  }
+
+  fn sin(self) -> MathResult<Self> {
+    Ok(
+      self
+        .with_precision(GUARD + self.magnitude(), |x, unit| sin_cos(x, unit).0),
+    )
+  }
+
+  fn cos(self) -> MathResult<Self> {
+    Ok(
+      self
+        .with_precision(GUARD + self.magnitude(), |x, unit| sin_cos(x, unit).1),
+    )
+  }
+
+  fn tan(self) -> MathResult<Self> {
+    let cos = self.clone().cos()?;
+    // Within rounding error of a pole, as at `pi / 2`, which is as close as
+    // a rounded angle can get
+    if cos.scaled.abs() <= BigInt::from(10) {
+      return Err(MathError::DivisionByZero(Span::default()));
+    }
+    // Near a pole `cos` has leading zeros in place of digits, and dividing
+    // by it scales its error up by as much again
+    let zeros =
+      Self::SCALE.saturating_sub(cos.scaled.abs().to_string().len() as u32);
+    let extra = GUARD + self.magnitude() + zeros * 2;
+    Ok(self.with_precision(extra, |x, unit| {
+      let (sin, cos) = sin_cos(x, unit);
+      div(&sin, &cos, unit)
+    }))
+  }
+
+  fn asin(self) -> MathResult<Self> {
+    let unit = Self::unit();
+    match self.scaled.abs().cmp(&unit) {
+      Ordering::Greater => complex(),
+      Ordering::Equal => {
+        let half_pi = Self::constant("pi").unwrap() / Self::from_u8(2).unwrap();
+        match self.scaled.is_negative() {
+          true => Ok(-half_pi),
+          false => Ok(half_pi),
+        }
+      },
+      Ordering::Less => Ok(self.with_precision(GUARD, |x, unit| {
+        let cos = sqrt(&(unit - mul(x, x, unit)), unit);
+        atan(&div(x, &cos, unit), unit)
+      })),
+    }
+  }
+
+  fn acos(self) -> MathResult<Self> {
+    let half_pi = Self::constant("pi").unwrap() / Self::from_u8(2).unwrap();
+    Ok(half_pi - self.asin()?)
+  }
+
+  fn atan(self) -> MathResult<Self> {
+    Ok(self.with_precision(GUARD, atan))
+  }
+
+  fn atan2(self, x: Self) -> MathResult<Self> {
+    let pi = Self::constant("pi").unwrap();
+    match (x.scaled.sign(), self.scaled.is_negative()) {
+      (num::bigint::Sign::Plus, _) => (self / x).atan(),
+      (num::bigint::Sign::Minus, false) => Ok((self / x).atan()? + pi),
+      (num::bigint::Sign::Minus, true) => Ok((self / x).atan()? - pi),
+      (num::bigint::Sign::NoSign, _) if self.is_zero() => Ok(self),
+      (num::bigint::Sign::NoSign, negative) => {
+        let half_pi = pi / Self::from_u8(2).unwrap();
+        match negative {
+          true => Ok(-half_pi),
+          false => Ok(half_pi),
+        }
+      },
+    }
+  }
+}
+
+/// Equal in value, whether or not either is a constant
+impl<const DIGITS: u32> PartialEq for Decimal<DIGITS> {
+  fn eq(&self, other: &Self) -> bool {
+    self.scaled == other.scaled
+  }
+}
+
+impl<const DIGITS: u32> Eq for Decimal<DIGITS> {}
+
+/// Rounded to `DIGITS` places, without trailing zeros
+impl<const DIGITS: u32> std::fmt::Display for Decimal<DIGITS> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let rounded = div_round(self.scaled.clone(), &pow10(GUARD));
+    let sign = if rounded.is_negative() { "-" } else { "" };
+    let digits =
+      format!("{:0>width$}", rounded.abs(), width = DIGITS as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - DIGITS as usize);
+    match fraction.trim_end_matches('0') {
+      "" => write!(f, "{sign}{whole}"),
+      fraction => write!(f, "{sign}{whole}.{fraction}"),
+    }
+  }
+}
+
+impl<const DIGITS: u32> Add for Decimal<DIGITS> {
+  type Output = Self;
+
+  fn add(self, other: Self) -> Self {
+    Self::from_scaled(self.scaled + other.scaled)
+  }
+}
+
+impl<const DIGITS: u32> Sub for Decimal<DIGITS> {
+  type Output = Self;
+
+  fn sub(self, other: Self) -> Self {
+    Self::from_scaled(self.scaled - other.scaled)
+  }
+}
+
+impl<const DIGITS: u32> Mul for Decimal<DIGITS> {
+  type Output = Self;
+
+  fn mul(self, other: Self) -> Self {
+    Self::from_scaled(mul(&self.scaled, &other.scaled, &Self::unit()))
+  }
+}
+
+/// # Panics
+///
+/// When dividing by zero, like integer division
+impl<const DIGITS: u32> Div for Decimal<DIGITS> {
+  type Output = Self;
+
+  fn div(self, other: Self) -> Self {
+    Self::from_scaled(div(&self.scaled, &other.scaled, &Self::unit()))
+  }
+}
+
+impl<const DIGITS: u32> Neg for Decimal<DIGITS> {
+  type Output = Self;
+
+  fn neg(self) -> Self {
+    Self::from_scaled(-self.scaled)
+  }
+}
+
+impl<const DIGITS: u32> Zero for Decimal<DIGITS> {
+  fn zero() -> Self {
+    Self::from_scaled(BigInt::zero())
+  }
+
+  fn is_zero(&self) -> bool {
+    self.scaled.is_zero()
+  }
+}
+
+impl<const DIGITS: u32> One for Decimal<DIGITS> {
+  fn one() -> Self {
+    Self::from_scaled(Self::unit())
+  }
+}
+
+impl<const DIGITS: u32> FromPrimitive for Decimal<DIGITS> {
+  fn from_i64(n: i64) -> Option<Self> {
+    Some(Self::from_scaled(BigInt::from(n) * Self::unit()))
+  }
+
+  fn from_u64(n: u64) -> Option<Self> {
+    Some(Self::from_scaled(BigInt::from(n) * Self::unit()))
+  }
+
+  fn from_f64(n: f64) -> Option<Self> {
+    Self::from_number(n.into()).ok()
+  }
+}
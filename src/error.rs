@@ -1,32 +1,107 @@
 use thiserror::Error;
 
-use crate::number::Number;
+use crate::{functions::Arity, number::Number, span::Span};
 
 #[derive(Error, Debug)]
 pub enum MathError {
   // Tokenizer errors
   #[error("Unrecognized symbol")]
-  IllegalSymbol,
+  IllegalSymbol(Span),
   #[error("Unmatched wrappings")]
-  UnmatchedWrapping,
+  UnmatchedWrapping(Span),
   #[error("Cannot parse number")]
-  BadNumber,
+  BadNumber(Span),
+  #[error("Separators can only appear between function arguments")]
+  MisplacedSeparator(Span),
+  // Syntax errors
+  #[error("Expected an operand")]
+  MissingOperand(Span),
+  #[error("Unexpected {0}")]
+  UnexpectedToken(String, Span),
+  #[error("Only variables and functions can be assigned to")]
+  InvalidAssignment(Span),
   // Computation errors
   #[error("Division by zero is undefined")]
-  DivisionByZero,
+  DivisionByZero(Span),
   #[error("Result too big to compute")]
-  TooBig,
+  TooBig(Span),
   #[error("No input")]
-  NoInput,
+  NoInput(Span),
   #[error("The {0} operation is undefined")]
-  UndefinedOperation(String),
+  UndefinedOperation(String, Span),
   #[error("The variable {0} is undefined")]
-  UndefinedVariable(String),
+  UndefinedVariable(String, Span),
   #[error("The function {0}() is undefined")]
-  UndefinedFunction(String),
+  UndefinedFunction(String, Span),
+  #[error("The function {0}() expects {1} arguments, got {2}")]
+  ArgumentCount(String, Arity, usize, Span),
+  #[error("Too many nested function calls")]
+  TooDeep(Span),
+  #[error("Result is not a real number")]
+  ComplexResult(Span),
   // Unknown
   #[error("Unknown error")]
-  Undefined,
+  Undefined(Span),
 }
 
-pub type MathResult = Result<Number, MathError>;
+impl MathError {
+  /// Location in the input the error refers to
+  pub fn span(&self) -> Span {
+    match self {
+      MathError::IllegalSymbol(span)
+      | MathError::UnmatchedWrapping(span)
+      | MathError::BadNumber(span)
+      | MathError::MisplacedSeparator(span)
+      | MathError::MissingOperand(span)
+      | MathError::UnexpectedToken(_, span)
+      | MathError::InvalidAssignment(span)
+      | MathError::DivisionByZero(span)
+      | MathError::TooBig(span)
+      | MathError::NoInput(span)
+      | MathError::UndefinedOperation(_, span)
+      | MathError::UndefinedVariable(_, span)
+      | MathError::UndefinedFunction(_, span)
+      | MathError::ArgumentCount(_, _, _, span)
+      | MathError::TooDeep(span)
+      | MathError::ComplexResult(span)
+      | MathError::Undefined(span) => *span,
+    }
+  }
+
+  fn span_mut(&mut self) -> &mut Span {
+    match self {
+      MathError::IllegalSymbol(span)
+      | MathError::UnmatchedWrapping(span)
+      | MathError::BadNumber(span)
+      | MathError::MisplacedSeparator(span)
+      | MathError::MissingOperand(span)
+      | MathError::UnexpectedToken(_, span)
+      | MathError::InvalidAssignment(span)
+      | MathError::DivisionByZero(span)
+      | MathError::TooBig(span)
+      | MathError::NoInput(span)
+      | MathError::UndefinedOperation(_, span)
+      | MathError::UndefinedVariable(_, span)
+      | MathError::UndefinedFunction(_, span)
+      | MathError::ArgumentCount(_, _, _, span)
+      | MathError::TooDeep(span)
+      | MathError::ComplexResult(span)
+      | MathError::Undefined(span) => span,
+    }
+  }
+
+  /// Attaches `span` if the error doesn't have a location yet
+  pub fn or_span(mut self, span: Span) -> Self {
+    if self.span().is_empty() {
+      *self.span_mut() = span;
+    }
+    self
+  }
+
+  /// Formats the error message above the offending part of `input`
+  pub fn render(&self, input: &str) -> String {
+    format!("{}\n{}", self, self.span().underline(input))
+  }
+}
+
+pub type MathResult<N = Number> = Result<N, MathError>;
@@ -0,0 +1,92 @@
+use std::str::FromStr;
+
+use crate::{
+  context::Context,
+  error::{MathError, MathResult},
+  lexer,
+  number::Number,
+  operator::OperatorSet,
+  parse::Ast,
+  span::Span,
+  token,
+  variables::Variable,
+  visit::Walker,
+};
+
+/// An expression parsed once, to be evaluated many times with different
+/// variable values, as when plotting. Only the default complex backend is
+/// supported, other backends go through [crate::evaluate_as]
+#[derive(Debug, Clone)]
+pub struct Expression {
+  ast: Ast,
+  variables: Vec<String>,
+}
+
+impl Expression {
+  pub fn new(input: &str) -> Result<Self, MathError> {
+    let op_set = OperatorSet::default();
+    let lexemes = lexer::lex(input.to_string(), &op_set);
+    Ok(Self::from_ast(Ast::new(token::tokenize(
+      &op_set, lexemes,
+    )?)?))
+  }
+
+  pub fn from_ast(ast: Ast) -> Self {
+    let mut free = FreeVariables {
+      constants: Variable::default_set().into_keys().collect(),
+      found: vec![],
+    };
+    ast.root.walk(&mut free);
+    Self {
+      ast,
+      variables: free.found,
+    }
+  }
+
+  pub fn ast(&self) -> &Ast {
+    &self.ast
+  }
+
+  /// Variables the expression reads, other than built in constants like
+  /// `pi`, in order of first appearance
+  pub fn variables(&self) -> &[String] {
+    &self.variables
+  }
+
+  /// Unlike [crate::evaluate], results aren't rounded
+  pub fn evaluate(&self, ctx: &Context) -> MathResult {
+    self.ast.evaluate(ctx)
+  }
+
+  /// Evaluates with `values` lined up with [Expression::variables], which
+  /// skips looking them up in `ctx`. Anything without a value, like
+  /// constants and functions, still comes from `ctx`
+  pub fn evaluate_with(&self, ctx: &Context, values: &[Number]) -> MathResult {
+    self.ast.root.evaluate_with(ctx, &self.variables, values)
+  }
+}
+
+impl FromStr for Expression {
+  type Err = MathError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::new(s)
+  }
+}
+
+/// Collects variable names in order of first appearance
+struct FreeVariables {
+  constants: Vec<String>,
+  found: Vec<String>,
+}
+
+impl Walker for FreeVariables {
+  type Output = ();
+
+  fn visit_variable(&mut self, name: &str, _span: Span) {
+    let name = name.to_string();
+    if !self.constants.contains(&name) && !self.found.contains(&name) {
+      self.found.push(name);
+    }
+  }
+}
@@ -1,88 +1,226 @@
-use std::collections::HashMap;
+use std::{
+  cmp::Ordering,
+  collections::{HashMap, HashSet},
+  sync::OnceLock,
+};
 
 use crate::{
   context::Context,
-  error::MathResult,
-  number::{sanitize_result, Number},
+  error::{MathError, MathResult},
+  number::{sanitize_result, Number, Numeric},
   parse::Ast,
+  span::Span,
 };
 
+/// Number of arguments a function accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+  Exact(usize),
+  AtLeast(usize),
+}
+
+impl Arity {
+  pub fn accepts(&self, count: usize) -> bool {
+    match self {
+      Arity::Exact(n) => count == *n,
+      Arity::AtLeast(n) => count >= *n,
+    }
+  }
+}
+
+impl std::fmt::Display for Arity {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Arity::Exact(n) => write!(f, "{n}"),
+      Arity::AtLeast(n) => write!(f, "at least {n}"),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
-pub enum Function {
-  SoftwareDefined(fn(Number) -> MathResult),
-  AstDefined(Ast, String),
+pub enum Function<N: Numeric = Number> {
+  SoftwareDefined(fn(N) -> MathResult<N>),
+  /// Takes exactly the given number of arguments
+  MultiArgument(usize, fn(&[N]) -> MathResult<N>),
+  /// Takes at least the given number of arguments
+  Variadic(usize, fn(&[N]) -> MathResult<N>),
+  /// Body along with the names of its parameters
+  AstDefined(Ast<N>, Vec<String>),
 }
 
-impl Function {
-  pub fn compute(&self, operand: MathResult) -> MathResult {
+impl<N: Numeric> Function<N> {
+  pub fn arity(&self) -> Arity {
     match self {
-      Function::SoftwareDefined(func) => (func)(operand?),
-      Function::AstDefined(ast, var_name) => {
-        let mut ctx = Context::new();
-        ctx.assign_variable(var_name.to_string(), operand?);
+      Function::SoftwareDefined(_) => Arity::Exact(1),
+      Function::AstDefined(_, params) => Arity::Exact(params.len()),
+      Function::MultiArgument(n, _) => Arity::Exact(*n),
+      Function::Variadic(n, _) => Arity::AtLeast(*n),
+    }
+  }
+
+  /// Arguments are expected to match [Function::arity]. User defined bodies
+  /// run in a new scope on top of `scope`, with parameters shadowing the
+  /// names in it
+  pub fn compute(
+    &self,
+    operands: &[N],
+    scope: &Context<N>,
+    depth: usize,
+  ) -> MathResult<N> {
+    match self {
+      Function::SoftwareDefined(func) => (func)(operands[0].clone()),
+      Function::MultiArgument(_, func) | Function::Variadic(_, func) => {
+        (func)(operands)
+      },
+      Function::AstDefined(ast, params) => {
+        let mut ctx = Context::child(scope, depth);
+        for (name, value) in params.iter().zip(operands) {
+          ctx.assign_variable(name.to_string(), value.clone());
+        }
         ast.evaluate(&ctx)
       },
     }
   }
 
-  pub fn default_set() -> HashMap<String, Function> {
+  /// Built in functions, for any backend
+  pub fn builtins() -> HashMap<String, Function<N>> {
     let mut map = HashMap::new();
-    map.insert("sin".to_string(), _SIN_FUNC);
-    map.insert("cos".to_string(), _COS_FUNC);
-    map.insert("tan".to_string(), _TAN_FUNC);
-    map.insert("csc".to_string(), _CSC_FUNC);
-    map.insert("sec".to_string(), _SEC_FUNC);
-    map.insert("cot".to_string(), _COT_FUNC);
-
-    map.insert("arcsin".to_string(), _ASIN_FUNC);
-    map.insert("arccos".to_string(), _ACOS_FUNC);
-    map.insert("arctan".to_string(), _ATAN_FUNC);
-
-    map.insert("ln".to_string(), _LN_FUNC);
-    map.insert("abs".to_string(), _ABS_FUNC);
-
-    map.insert("sqrt".to_string(), _SQRT_FUNC);
+    // trig
+    map.insert("sin".to_string(), Function::SoftwareDefined(N::sin));
+    map.insert("cos".to_string(), Function::SoftwareDefined(N::cos));
+    map.insert("tan".to_string(), Function::SoftwareDefined(N::tan));
+    map.insert(
+      "csc".to_string(),
+      Function::SoftwareDefined(|n| reciprocal(n.sin()?)),
+    );
+    map.insert(
+      "sec".to_string(),
+      Function::SoftwareDefined(|n| reciprocal(n.cos()?)),
+    );
+    map.insert(
+      "cot".to_string(),
+      Function::SoftwareDefined(|n| reciprocal(n.tan()?)),
+    );
+
+    // inverse trig
+    map.insert("arcsin".to_string(), Function::SoftwareDefined(N::asin));
+    map.insert("arccos".to_string(), Function::SoftwareDefined(N::acos));
+    map.insert("arctan".to_string(), Function::SoftwareDefined(N::atan));
+
+    map.insert("ln".to_string(), Function::SoftwareDefined(N::ln));
+    map.insert("exp".to_string(), Function::SoftwareDefined(N::exp));
+    map.insert(
+      "abs".to_string(),
+      Function::SoftwareDefined(|n| Ok(n.abs())),
+    );
+
+    map.insert("sqrt".to_string(), Function::SoftwareDefined(N::sqrt));
+    map.insert("gamma".to_string(), Function::SoftwareDefined(N::gamma));
+
+    // log with a base
+    map.insert(
+      "log".to_string(),
+      Function::MultiArgument(2, |n| {
+        let (base, num) = (n[0].clone().ln()?, n[1].clone().ln()?);
+        match base.is_zero() {
+          true => Err(MathError::DivisionByZero(Span::default())),
+          false => (num / base).sanitize(),
+        }
+      }),
+    );
+
+    // real two argument functions
+    map.insert(
+      "atan2".to_string(),
+      Function::MultiArgument(2, |n| n[0].clone().atan2(n[1].clone())),
+    );
+    map.insert(
+      "hypot".to_string(),
+      Function::MultiArgument(2, |n| {
+        let n = real_operands(n, "hypot")?;
+        N::from_number(sanitize_result(n[0].hypot(n[1]).into())?)
+      }),
+    );
+
+    // extremes
+    map.insert(
+      "max".to_string(),
+      Function::Variadic(1, |n| extreme(n, "max", Ordering::Greater)),
+    );
+    map.insert(
+      "min".to_string(),
+      Function::Variadic(1, |n| extreme(n, "min", Ordering::Less)),
+    );
     map
   }
 }
 
-// trig
-const _SIN_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.sin()));
-
-const _COS_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.cos()));
-
-const _TAN_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.tan()));
-
-const _CSC_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.sin().inv()));
-
-const _SEC_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.cos().inv()));
-
-const _COT_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.tan().inv()));
-
-// inverse trig
-const _ASIN_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.asin()));
-
-const _ACOS_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.acos()));
+impl Function {
+  /// Whether `name` is one of the functions in [Function::builtins]. Every
+  /// backend has the same names, so this is only here on the default one
+  pub fn is_builtin(name: &str) -> bool {
+    static NAMES: OnceLock<HashSet<String>> = OnceLock::new();
+    NAMES
+      .get_or_init(|| Self::builtins().into_keys().collect())
+      .contains(name)
+  }
 
-const _ATAN_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.atan()));
+  /// [Function::builtins] for [Number]
+  pub fn default_set() -> HashMap<String, Function> {
+    Self::builtins()
+  }
+}
 
-// log
-const _LN_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.ln()));
+/// Real parts of the operands, for functions that only make sense on the
+/// real line
+fn real_operands<N: Numeric>(
+  operands: &[N],
+  name: &str,
+) -> Result<Vec<f64>, MathError> {
+  operands
+    .iter()
+    .map(|n| match n.to_number() {
+      n if n.im == 0.0 => Ok(n.re),
+      _ => Err(MathError::UndefinedOperation(
+        format!("complex {name}"),
+        Span::default(),
+      )),
+    })
+    .collect()
+}
 
-// abs
-const _ABS_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.norm().into()));
+fn reciprocal<N: Numeric>(num: N) -> MathResult<N> {
+  match num.is_zero() {
+    true => Err(MathError::DivisionByZero(Span::default())),
+    false => (N::one() / num).sanitize(),
+  }
+}
 
-// sqrt
-const _SQRT_FUNC: Function =
-  Function::SoftwareDefined(|n| sanitize_result(n.sqrt()));
+/// The operand furthest in the direction of `wanted`
+fn extreme<N: Numeric>(
+  operands: &[N],
+  name: &str,
+  wanted: Ordering,
+) -> MathResult<N> {
+  let mut best = operands[0].clone();
+  for operand in &operands[1..] {
+    match operand.real_cmp(&best) {
+      Some(order) if order == wanted => best = operand.clone(),
+      Some(_) => {},
+      None => {
+        return Err(MathError::UndefinedOperation(
+          format!("complex {name}"),
+          Span::default(),
+        ))
+      },
+    }
+  }
+  // A lone operand still has to be real
+  match best.real_cmp(&best) {
+    Some(_) => Ok(best),
+    None => Err(MathError::UndefinedOperation(
+      format!("complex {name}"),
+      Span::default(),
+    )),
+  }
+}
@@ -0,0 +1,544 @@
+use crate::{
+  context::Context,
+  functions::Function,
+  lexer::{self, Lexeme, Wrapping},
+  notation::{self, Layout},
+  number::Number,
+  operator::OperatorSet,
+  parse::{Ast, AstNode, Fixity, Statement},
+  span::{Span, Spanned},
+};
+
+/// Names written as Greek letters, both as variables and in LaTeX input
+pub const GREEK_LETTERS: [&str; 24] = [
+  "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta", "iota",
+  "kappa", "lambda", "mu", "nu", "xi", "omicron", "pi", "rho", "sigma", "tau",
+  "upsilon", "phi", "chi", "psi", "omega",
+];
+
+/// Built in functions with a LaTeX command of their own
+const NAMED_FUNCTIONS: [(&str, &str); 15] = [
+  ("sin", "\\sin"),
+  ("cos", "\\cos"),
+  ("tan", "\\tan"),
+  ("csc", "\\csc"),
+  ("sec", "\\sec"),
+  ("cot", "\\cot"),
+  ("arcsin", "\\arcsin"),
+  ("arccos", "\\arccos"),
+  ("arctan", "\\arctan"),
+  ("ln", "\\ln"),
+  ("exp", "\\exp"),
+  ("log", "\\log"),
+  ("max", "\\max"),
+  ("min", "\\min"),
+  ("gamma", "\\Gamma"),
+];
+
+impl Ast {
+  /// Renders the expression as LaTeX math, without the surrounding `$`s.
+  /// Custom functions use the names registered in `ctx`
+  pub fn to_latex(&self, ctx: &Context) -> String {
+    let mut out = String::new();
+    write_node(&mut out, &self.root, ctx);
+    out
+  }
+}
+
+impl Statement {
+  pub fn to_latex(&self, ctx: &Context) -> String {
+    match self {
+      Statement::Expression(ast) => ast.to_latex(ctx),
+      Statement::Assignment { name, value, .. } => {
+        format!("{} = {}", variable_latex(name), value.to_latex(ctx))
+      },
+      Statement::FunctionDefinition {
+        name, params, body, ..
+      } => {
+        let params: Vec<_> = params.iter().map(|p| variable_latex(p)).collect();
+        format!(
+          "{}\\left({}\\right) = {}",
+          function_latex(name, ctx),
+          params.join(", "),
+          body.to_latex(ctx)
+        )
+      },
+    }
+  }
+}
+
+fn number_latex(value: Number) -> String {
+  let imaginary = match value.im.abs() {
+    1.0 => "i".to_string(),
+    im => format!("{im}i"),
+  };
+  match (value.re, value.im) {
+    (re, 0.0) => format!("{re}"),
+    (0.0, im) if im < 0.0 => format!("-{imaginary}"),
+    (0.0, _) => imaginary,
+    (re, im) if im < 0.0 => format!("{re} - {imaginary}"),
+    (re, _) => format!("{re} + {imaginary}"),
+  }
+}
+
+fn variable_latex(name: &str) -> String {
+  if GREEK_LETTERS.contains(&name) {
+    format!("\\{name}")
+  } else if name.len() == 1 {
+    name.to_string()
+  } else {
+    format!("\\mathrm{{{name}}}")
+  }
+}
+
+fn function_latex(name: &str, ctx: &Context) -> String {
+  if let Some(latex) = ctx.latex_name(name) {
+    return latex.to_string();
+  }
+  match NAMED_FUNCTIONS.iter().find(|(n, _)| *n == name) {
+    Some((_, latex)) => latex.to_string(),
+    None if name.len() == 1 => name.to_string(),
+    None => format!("\\operatorname{{{name}}}"),
+  }
+}
+
+fn write_wrapped(out: &mut String, node: &AstNode, ctx: &Context, wrap: bool) {
+  if wrap {
+    out.push_str("\\left(");
+    write_node(out, node, ctx);
+    out.push_str("\\right)");
+  } else {
+    write_node(out, node, ctx);
+  }
+}
+
+fn write_node(out: &mut String, node: &AstNode, ctx: &Context) {
+  match node {
+    AstNode::Constant { value, .. } => out.push_str(&number_latex(*value)),
+    AstNode::Variable { name, .. } => out.push_str(&variable_latex(name)),
+    AstNode::Call { name, args, .. } => {
+      match (name.as_str(), args.as_slice()) {
+        ("sqrt", [arg]) => {
+          out.push_str("\\sqrt{");
+          write_node(out, arg, ctx);
+          out.push('}');
+        },
+        ("abs", [arg]) => {
+          out.push_str("\\left|");
+          write_node(out, arg, ctx);
+          out.push_str("\\right|");
+        },
+        ("log", [base, arg]) if ctx.latex_name(name).is_none() => {
+          out.push_str("\\log_{");
+          write_node(out, base, ctx);
+          out.push_str("}\\left(");
+          write_node(out, arg, ctx);
+          out.push_str("\\right)");
+        },
+        _ => {
+          out.push_str(&function_latex(name, ctx));
+          out.push_str("\\left(");
+          for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+              out.push_str(", ");
+            }
+            write_node(out, arg, ctx);
+          }
+          out.push_str("\\right)");
+        },
+      }
+    },
+    AstNode::Unary {
+      op,
+      fixity: Fixity::Prefix,
+      operand,
+      ..
+    } => match op.symbol {
+      "√" => {
+        out.push_str("\\sqrt{");
+        write_node(out, operand, ctx);
+        out.push('}');
+      },
+      symbol => {
+        out.push_str(symbol);
+        let wrap = notation::wrap_prefix_operand(Layout::Typeset, op, operand);
+        write_wrapped(out, operand, ctx, wrap);
+      },
+    },
+    AstNode::Unary {
+      op,
+      fixity: Fixity::Postfix,
+      operand,
+      ..
+    } => {
+      let wrap = notation::wrap_postfix_operand(Layout::Typeset, operand);
+      write_wrapped(out, operand, ctx, wrap);
+      out.push_str(match op.symbol {
+        "%" => "\\%",
+        symbol => symbol,
+      });
+    },
+    AstNode::Binary {
+      op, left, right, ..
+    } => match op.symbol {
+      "/" => {
+        out.push_str("\\frac{");
+        write_node(out, left, ctx);
+        out.push_str("}{");
+        write_node(out, right, ctx);
+        out.push('}');
+      },
+      "^" => {
+        let wrap = notation::wrap_base(left);
+        write_wrapped(out, left, ctx, wrap);
+        out.push_str("^{");
+        write_node(out, right, ctx);
+        out.push('}');
+      },
+      symbol => {
+        let wrap_left = notation::wrap_left(Layout::Typeset, op, left);
+        let wrap_right = notation::wrap_right(Layout::Typeset, op, right);
+        write_wrapped(out, left, ctx, wrap_left);
+        out.push_str(match symbol {
+          "*" => " \\cdot ",
+          "+" => " + ",
+          "-" => " - ",
+          _ => symbol,
+        });
+        write_wrapped(out, right, ctx, wrap_right);
+      },
+    },
+    AstNode::Group {
+      wrapping: Wrapping::Bar,
+      inner,
+      ..
+    } => {
+      out.push_str("\\left|");
+      write_node(out, inner, ctx);
+      out.push_str("\\right|");
+    },
+    AstNode::Group { inner, .. } => write_node(out, inner, ctx),
+  }
+}
+
+/// Turns a practical subset of LaTeX math into the same lexemes [lexer::lex]
+/// produces for the equivalent plain input. Spans point into the LaTeX
+pub fn lex<N>(input: String, op_set: &OperatorSet<N>) -> Vec<Spanned<Lexeme>> {
+  let mut lexer = LatexLexer {
+    input: &input,
+    chars: input.char_indices().collect(),
+    position: 0,
+    op_set,
+  };
+  let mut lexemes = vec![];
+  lexer.lex_sequence(&mut lexemes, None);
+  lexemes
+}
+
+struct LatexLexer<'a, N> {
+  input: &'a str,
+  chars: Vec<(usize, char)>,
+  position: usize,
+  op_set: &'a OperatorSet<N>,
+}
+
+/// `inner` wrapped in parentheses, so it stays one operand
+fn parenthesized(
+  inner: Vec<Spanned<Lexeme>>,
+  span: Span,
+) -> Vec<Spanned<Lexeme>> {
+  let mut lexemes = Vec::with_capacity(inner.len() + 2);
+  lexemes.push(Spanned::new(Lexeme::LeftWrap(Wrapping::Paren), span));
+  lexemes.extend(inner);
+  lexemes.push(Spanned::new(Lexeme::RightWrap(Wrapping::Paren), span));
+  lexemes
+}
+
+impl<N> LatexLexer<'_, N> {
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.position).map(|(_, c)| *c)
+  }
+
+  /// Byte offset of the next char
+  fn offset(&self) -> usize {
+    match self.chars.get(self.position) {
+      Some((offset, _)) => *offset,
+      None => self.input.len(),
+    }
+  }
+
+  fn skip_whitespace(&mut self) {
+    while self.peek().is_some_and(char::is_whitespace) {
+      self.position += 1;
+    }
+  }
+
+  /// Lexes until `close`, which is consumed. Returns whether it was found
+  fn lex_sequence(
+    &mut self,
+    out: &mut Vec<Spanned<Lexeme>>,
+    close: Option<char>,
+  ) -> bool {
+    while let Some(next) = self.peek() {
+      let start = self.offset();
+      match next {
+        c if Some(c) == close => {
+          self.position += 1;
+          return true;
+        },
+        '{' => {
+          self.position += 1;
+          let span = Span::new(start, start + 1);
+          out.push(Spanned::new(Lexeme::LeftWrap(Wrapping::Curly), span));
+          if self.lex_sequence(out, Some('}')) {
+            let span = Span::new(self.offset() - 1, self.offset());
+            out.push(Spanned::new(Lexeme::RightWrap(Wrapping::Curly), span));
+          }
+        },
+        '}' => {
+          self.position += 1;
+          let span = Span::new(start, start + 1);
+          out.push(Spanned::new(Lexeme::RightWrap(Wrapping::Curly), span));
+        },
+        '\\' => self.command(out),
+        '^' => {
+          self.position += 1;
+          let span = Span::new(start, start + 1);
+          out.push(Spanned::new(Lexeme::Special("^".to_string()), span));
+          let exponent = self.argument();
+          out.extend(parenthesized(exponent, span));
+        },
+        c if c.is_ascii_alphabetic() => self.letter(out),
+        _ => self.plain(out, close),
+      }
+    }
+    false
+  }
+
+  /// A braced group, or else a single char or command, as taken by `^`,
+  /// `\frac` and `\sqrt`
+  fn argument(&mut self) -> Vec<Spanned<Lexeme>> {
+    self.skip_whitespace();
+    let start = self.offset();
+    let mut out = vec![];
+    match self.peek() {
+      Some('{') => {
+        self.position += 1;
+        if !self.lex_sequence(&mut out, Some('}')) {
+          // Left open so the tokenizer reports it
+          let span = Span::new(start, start + 1);
+          out.insert(0, Spanned::new(Lexeme::LeftWrap(Wrapping::Curly), span));
+        }
+      },
+      Some('\\') => self.command(&mut out),
+      Some(c) if c.is_ascii_alphabetic() => self.letter(&mut out),
+      Some(c) => {
+        self.position += 1;
+        let span = Span::new(start, start + c.len_utf8());
+        let item = match c.is_ascii_digit() {
+          true => Lexeme::Number(c.to_string()),
+          false => Lexeme::Unknown(c),
+        };
+        out.push(Spanned::new(item, span));
+      },
+      None => {},
+    }
+    out
+  }
+
+  /// Single letters are separate variables, as in `2xy`, and may carry a
+  /// subscript like `x_1` or `x_{max}`
+  fn letter(&mut self, out: &mut Vec<Spanned<Lexeme>>) {
+    let start = self.offset();
+    let mut name = self.peek().unwrap().to_string();
+    self.position += 1;
+    if self.peek() == Some('_') {
+      self.position += 1;
+      name.push('_');
+      match self.peek() {
+        Some('{') => {
+          self.position += 1;
+          name.push_str(self.raw_until('}').trim());
+        },
+        Some(c) if c.is_ascii_alphanumeric() => {
+          self.position += 1;
+          name.push(c);
+        },
+        _ => {},
+      }
+    }
+    let span = Span::new(start, self.offset());
+    out.push(Spanned::new(Lexeme::Identifier(name), span));
+  }
+
+  /// An opening `(` or `[`, possibly sized with `\left`
+  fn open_wrapping(&mut self) -> Option<Spanned<Lexeme>> {
+    self.skip_whitespace();
+    let position = self.position;
+    let start = self.offset();
+    if self.input[start..].starts_with("\\left") {
+      self.position += "\\left".len();
+      self.skip_whitespace();
+    }
+    let wrapping = match self.peek() {
+      Some('(') => Wrapping::Paren,
+      Some('[') => Wrapping::Square,
+      _ => {
+        self.position = position;
+        return None;
+      },
+    };
+    self.position += 1;
+    let span = Span::new(start, self.offset());
+    Some(Spanned::new(Lexeme::LeftWrap(wrapping), span))
+  }
+
+  /// Text up to `close`, which is consumed
+  fn raw_until(&mut self, close: char) -> &str {
+    let start = self.offset();
+    while self.peek().is_some_and(|c| c != close) {
+      self.position += 1;
+    }
+    let end = self.offset();
+    if self.peek().is_some() {
+      self.position += 1;
+    }
+    &self.input[start..end]
+  }
+
+  /// Runs of numbers, operators and wrappings read the same as plain input
+  fn plain(&mut self, out: &mut Vec<Spanned<Lexeme>>, close: Option<char>) {
+    let start = self.offset();
+    while let Some(c) = self.peek() {
+      if matches!(c, '\\' | '{' | '}' | '^')
+        || c.is_ascii_alphabetic()
+        || Some(c) == close
+      {
+        break;
+      }
+      self.position += 1;
+    }
+    let run = self.input[start..self.offset()].to_string();
+    out.extend(lexer::lex(run, self.op_set).into_iter().map(|l| {
+      let span = Span::new(l.span.start + start, l.span.end + start);
+      Spanned::new(l.item, span)
+    }));
+  }
+
+  fn command(&mut self, out: &mut Vec<Spanned<Lexeme>>) {
+    let start = self.offset();
+    self.position += 1;
+    let name_start = self.offset();
+    match self.peek() {
+      Some(c) if c.is_ascii_alphabetic() => {
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+          self.position += 1;
+        }
+      },
+      Some(_) => self.position += 1,
+      None => {},
+    }
+    let name = &self.input[name_start..self.offset()];
+    let span = Span::new(start, self.offset());
+    let push =
+      |out: &mut Vec<Spanned<Lexeme>>, item| out.push(Spanned::new(item, span));
+    match name {
+      "frac" | "dfrac" | "tfrac" => {
+        let numerator = parenthesized(self.argument(), span);
+        let denominator = parenthesized(self.argument(), span);
+        let mut fraction = numerator;
+        fraction.push(Spanned::new(Lexeme::Special("/".to_string()), span));
+        fraction.extend(denominator);
+        out.extend(parenthesized(fraction, span));
+      },
+      "sqrt" => {
+        self.skip_whitespace();
+        let mut index = None;
+        if self.peek() == Some('[') {
+          self.position += 1;
+          let mut lexemes = vec![];
+          self.lex_sequence(&mut lexemes, Some(']'));
+          index = Some(lexemes);
+        }
+        let radicand = parenthesized(self.argument(), span);
+        match index {
+          // `x^(1/n)`
+          Some(index) => {
+            let mut power = radicand;
+            power.push(Spanned::new(Lexeme::Special("^".to_string()), span));
+            let mut exponent = vec![
+              Spanned::new(Lexeme::Number("1".to_string()), span),
+              Spanned::new(Lexeme::Special("/".to_string()), span),
+            ];
+            exponent.extend(parenthesized(index, span));
+            power.extend(parenthesized(exponent, span));
+            out.extend(parenthesized(power, span));
+          },
+          None => {
+            push(out, Lexeme::Identifier("sqrt".to_string()));
+            out.extend(radicand);
+          },
+        }
+      },
+      // `\log_{b}(x)` is `log(b, x)`
+      "log" if self.peek() == Some('_') => {
+        self.position += 1;
+        let mut args = self.argument();
+        args.push(Spanned::new(Lexeme::Separator, span));
+        push(out, Lexeme::Identifier("log".to_string()));
+        match self.open_wrapping() {
+          // The operand's own closing wrapping ends the call
+          Some(open) => {
+            out.push(open);
+            out.extend(args);
+          },
+          None => {
+            args.extend(self.argument());
+            out.extend(parenthesized(args, span));
+          },
+        }
+      },
+      "cdot" | "times" | "ast" => push(out, Lexeme::Special("*".to_string())),
+      "div" => push(out, Lexeme::Special("/".to_string())),
+      "%" => push(out, Lexeme::Special("%".to_string())),
+      "{" => push(out, Lexeme::LeftWrap(Wrapping::Curly)),
+      "}" => push(out, Lexeme::RightWrap(Wrapping::Curly)),
+      // Sizing only, the delimiter that follows is lexed as usual
+      "left" | "right" | "bigl" | "bigr" | "Bigl" | "Bigr" => {
+        self.skip_whitespace();
+        if self.peek() == Some('.') {
+          self.position += 1;
+        }
+      },
+      // Spacing
+      "," | ";" | ":" | "!" | " " | "quad" | "qquad" => {},
+      "operatorname" | "mathrm" | "text" => {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+          return push(out, Lexeme::Unknown('\\'));
+        }
+        self.position += 1;
+        let name = self.raw_until('}').trim().to_string();
+        let span = Span::new(start, self.offset());
+        out.push(Spanned::new(Lexeme::Identifier(name), span));
+      },
+      name => {
+        let command = format!("\\{name}");
+        let identifier =
+          match NAMED_FUNCTIONS.iter().find(|(_, latex)| *latex == command) {
+            Some((function, _)) => function.to_string(),
+            // Letters like `\gamma` stay variables next to the function
+            None
+              if Function::is_builtin(name)
+                && GREEK_LETTERS.contains(&name) =>
+            {
+              return push(out, Lexeme::Variable(name.to_string()));
+            },
+            None if GREEK_LETTERS.contains(&name) => name.to_string(),
+            None => return push(out, Lexeme::Unknown('\\')),
+          };
+        push(out, Lexeme::Identifier(identifier));
+      },
+    }
+  }
+}
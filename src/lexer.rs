@@ -1,19 +1,36 @@
-use std::{iter::Peekable, str::Chars};
+use std::{iter::Peekable, str::CharIndices};
+
+use crate::{
+  operator::OperatorSet,
+  span::{Span, Spanned},
+  unicode,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Wrapping {
   Paren,
   Curly,
   Square,
+  /// Absolute value bars, which open and close with the same char
+  Bar,
 }
 
 #[derive(Clone, PartialEq)]
 pub enum Lexeme {
   Number(String),
   Identifier(String),
-  Special(char),
+  /// A name that is never a function, like the `\gamma` in LaTeX input, even
+  /// where a function shares it
+  Variable(String),
+  Special(String),
   LeftWrap(Wrapping),
   RightWrap(Wrapping),
+  /// A `|`, which could either open or close absolute value bars
+  Bar,
+  Separator,
+  Assign,
+  /// Ends a statement, either `;` or a newline when lexing a program
+  Terminator,
   Unknown(char),
 }
 
@@ -22,31 +39,39 @@ impl std::fmt::Debug for Lexeme {
     match self {
       Lexeme::Number(s) => write!(f, "Number::{}", s),
       Lexeme::Identifier(s) => write!(f, "Ident::{}", s),
+      Lexeme::Variable(s) => write!(f, "Var::{}", s),
       Lexeme::Special(c) => write!(f, "Special::{}", c),
-      Lexeme::LeftWrap(w) => write!(
-        f,
-        "LWrap::{}",
-        match w {
-          Wrapping::Paren => '(',
-          Wrapping::Curly => '{',
-          Wrapping::Square => '[',
-        }
-      ),
-
-      Lexeme::RightWrap(w) => write!(
-        f,
-        "RWrap::{}",
-        match w {
-          Wrapping::Paren => ')',
-          Wrapping::Curly => '}',
-          Wrapping::Square => ']',
-        }
-      ),
+      Lexeme::LeftWrap(w) => write!(f, "LWrap::{}", w.open_char()),
+      Lexeme::RightWrap(w) => write!(f, "RWrap::{}", w.close_char()),
+      Lexeme::Bar => write!(f, "Bar::|"),
+      Lexeme::Separator => write!(f, "Sep::,"),
+      Lexeme::Assign => write!(f, "Assign::="),
+      Lexeme::Terminator => write!(f, "Term::;"),
       Lexeme::Unknown(c) => write!(f, "?::{}", c),
     }
   }
 }
 
+impl Wrapping {
+  pub const fn open_char(&self) -> char {
+    match self {
+      Wrapping::Paren => '(',
+      Wrapping::Curly => '{',
+      Wrapping::Square => '[',
+      Wrapping::Bar => '|',
+    }
+  }
+
+  pub const fn close_char(&self) -> char {
+    match self {
+      Wrapping::Paren => ')',
+      Wrapping::Curly => '}',
+      Wrapping::Square => ']',
+      Wrapping::Bar => '|',
+    }
+  }
+}
+
 /// Matches to grouping lexeme if applicable
 #[inline]
 fn match_wrapping_char(c: char) -> Option<Lexeme> {
@@ -61,48 +86,217 @@ fn match_wrapping_char(c: char) -> Option<Lexeme> {
   })
 }
 
-/// Collects contiguous chars using closure
+/// Collects contiguous chars using closure, returning them along with the
+/// byte offset one past the last char taken
 #[inline]
 fn grab_while(
-  char_stream: &mut Peekable<Chars<'_>>,
+  char_stream: &mut Peekable<CharIndices<'_>>,
+  mut end: usize,
   closure: impl Fn(&char) -> bool,
-) -> String {
+) -> (String, usize) {
   let mut word: Vec<char> = vec![];
-  while let Some(next) = char_stream.peek() {
+  while let Some((index, next)) = char_stream.peek() {
     if !closure(next) {
       break;
     }
-    word.push(next.clone());
+    end = index + next.len_utf8();
+    word.push(*next);
     char_stream.next();
   }
-  word.into_iter().collect::<String>()
+  (word.into_iter().collect::<String>(), end)
+}
+
+/// Collects an exponent suffix such as `e9`, `E-9` or `e+9` if one follows.
+/// The marker has to be followed by digits, so `2e` and `2e+x` still read as
+/// multiplication by the constant `e`
+fn grab_exponent(
+  char_stream: &mut Peekable<CharIndices<'_>>,
+  end: usize,
+) -> Option<(String, usize)> {
+  let mut lookahead = char_stream.clone();
+  let mut marker = String::new();
+  match lookahead.next() {
+    Some((_, c @ ('e' | 'E'))) => marker.push(c),
+    _ => return None,
+  }
+  if let Some(&(_, c @ ('+' | '-'))) = lookahead.peek() {
+    marker.push(c);
+    lookahead.next();
+  }
+  if !lookahead.peek().is_some_and(|(_, c)| c.is_ascii_digit()) {
+    return None;
+  }
+  *char_stream = lookahead;
+  let (digits, end) = grab_while(char_stream, end, |c| c.is_ascii_digit());
+  Some((marker + &digits, end))
+}
+
+/// Collects a prefixed integer literal such as `0xFF`, `0b1010` or `0o755`.
+/// The prefix only counts when a digit follows it, so `0x` on its own is still
+/// zero times `x`. Everything alphanumeric after the prefix is taken, letting
+/// the tokenizer reject `0b102` as a whole
+fn grab_radix_literal(
+  char_stream: &mut Peekable<CharIndices<'_>>,
+  end: usize,
+) -> Option<(String, usize)> {
+  let mut lookahead = char_stream.clone();
+  let marker = match lookahead.next() {
+    Some((_, c @ ('x' | 'X' | 'b' | 'B' | 'o' | 'O'))) => c,
+    _ => return None,
+  };
+  let starts_number = |c: &char| match marker {
+    'x' | 'X' => c.is_ascii_hexdigit(),
+    _ => c.is_ascii_digit(),
+  };
+  if !lookahead.peek().is_some_and(|(_, c)| starts_number(c)) {
+    return None;
+  }
+  *char_stream = lookahead;
+  let (digits, end) =
+    grab_while(char_stream, end, |c| c.is_ascii_alphanumeric() || *c == '_');
+  Some((format!("{marker}{digits}"), end))
+}
+
+/// Collects an imaginary unit suffix such as the `i` in `3i` or the `j` in
+/// `2.5j`. It has to end the word, so `3in` is still `3` times `in`
+fn grab_imaginary(
+  char_stream: &mut Peekable<CharIndices<'_>>,
+) -> Option<(char, usize)> {
+  let mut lookahead = char_stream.clone();
+  let (index, suffix) = match lookahead.next() {
+    Some((index, c @ ('i' | 'j'))) => (index, c),
+    _ => return None,
+  };
+  if lookahead
+    .peek()
+    .is_some_and(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+  {
+    return None;
+  }
+  *char_stream = lookahead;
+  Some((suffix, index + suffix.len_utf8()))
 }
 
-pub fn lex(input: String) -> Vec<Lexeme> {
+/// Lexes a single statement, where newlines are just whitespace
+pub fn lex<N>(input: String, op_set: &OperatorSet<N>) -> Vec<Spanned<Lexeme>> {
+  lex_with(input, op_set, false)
+}
+
+/// Like [lex], for a [Program](crate::parse::Program) where newlines end
+/// statements the way `;` does
+pub fn lex_program<N>(
+  input: String,
+  op_set: &OperatorSet<N>,
+) -> Vec<Spanned<Lexeme>> {
+  lex_with(input, op_set, true)
+}
+
+fn lex_with<N>(
+  input: String,
+  op_set: &OperatorSet<N>,
+  newline_terminates: bool,
+) -> Vec<Spanned<Lexeme>> {
   let mut lexemes = vec![];
-  let mut char_stream = input.chars().peekable();
-  while let Some(next) = char_stream.peek() {
-    let next = next.clone();
+  // Newlines inside wrappings continue the statement, as in `(a +\n 2)`
+  let mut open_wrappings = 0usize;
+  let mut char_stream = input.char_indices().peekable();
+  while let Some(&(start, next)) = char_stream.peek() {
+    let span = Span::new(start, start + next.len_utf8());
     // Operator
-    if Lexeme::SPECIAL_CHARS.contains(&next) {
-      lexemes.push(Lexeme::Special(next));
+    if let Some(symbol) = op_set.match_symbol(&input[start..]) {
+      let end = start + symbol.len();
+      lexemes.push(Spanned::new(
+        Lexeme::Special(symbol.to_string()),
+        Span::new(start, end),
+      ));
+      // Skip the rest of the symbol
+      while char_stream.next_if(|(index, _)| *index < end).is_some() {}
+      continue;
     }
     // Identifier
     else if next.is_ascii_alphabetic() {
-      let word = grab_while(&mut char_stream, |c| c.is_alphanumeric());
-      lexemes.push(Lexeme::Identifier(word));
+      let (word, end) =
+        grab_while(&mut char_stream, start, |c| c.is_ascii_alphanumeric());
+      lexemes.push(Spanned::new(
+        Lexeme::Identifier(word),
+        Span::new(start, end),
+      ));
       continue;
     }
     // Number
     else if next.is_ascii_digit() {
-      let word =
-        grab_while(&mut char_stream, |c| c.is_ascii_digit() || *c == '.');
-      lexemes.push(Lexeme::Number(word));
+      if next == '0' {
+        // Skip past the zero to look for a radix prefix
+        let mut lookahead = char_stream.clone();
+        lookahead.next();
+        if let Some((word, end)) = grab_radix_literal(&mut lookahead, start + 1)
+        {
+          char_stream = lookahead;
+          lexemes.push(Spanned::new(
+            Lexeme::Number(format!("0{word}")),
+            Span::new(start, end),
+          ));
+          continue;
+        }
+      }
+      let (mut word, mut end) = grab_while(&mut char_stream, start, |c| {
+        c.is_ascii_digit() || *c == '.' || *c == '_'
+      });
+      if let Some((exponent, exp_end)) = grab_exponent(&mut char_stream, end) {
+        word.push_str(&exponent);
+        end = exp_end;
+      }
+      if let Some((suffix, suffix_end)) = grab_imaginary(&mut char_stream) {
+        word.push(suffix);
+        end = suffix_end;
+      }
+      lexemes.push(Spanned::new(Lexeme::Number(word), Span::new(start, end)));
+      continue;
+    }
+    // Unicode operators and constants
+    else if let Some(symbol) = unicode::operator_alias(next) {
+      lexemes.push(Spanned::new(Lexeme::Special(symbol.to_string()), span));
+    } else if let Some(name) = unicode::constant_alias(next) {
+      lexemes.push(Spanned::new(Lexeme::Identifier(name.to_string()), span));
+    } else if unicode::is_imaginary_unit(next) {
+      lexemes.push(Spanned::new(Lexeme::Number("1i".to_string()), span));
+    }
+    // Superscript exponent, read as `^` followed by the plain number
+    else if unicode::superscript(next).is_some() {
+      let (word, end) = grab_while(&mut char_stream, start, |c| {
+        unicode::superscript(*c).is_some()
+      });
+      let exponent = word.chars().filter_map(unicode::superscript).collect();
+      let span = Span::new(start, end);
+      lexemes.push(Spanned::new(Lexeme::Special("^".to_string()), span));
+      lexemes.push(Spanned::new(Lexeme::Number(exponent), span));
       continue;
     }
     // Wrapping
     else if let Some(grp) = match_wrapping_char(next) {
-      lexemes.push(grp);
+      match grp {
+        Lexeme::LeftWrap(_) => open_wrappings += 1,
+        _ => open_wrappings = open_wrappings.saturating_sub(1),
+      }
+      lexemes.push(Spanned::new(grp, span));
+    }
+    // Absolute value bar, left for the tokenizer to pair up
+    else if next == '|' {
+      lexemes.push(Spanned::new(Lexeme::Bar, span));
+    }
+    // Argument separator
+    else if next == ',' {
+      lexemes.push(Spanned::new(Lexeme::Separator, span));
+    }
+    // Assignment
+    else if next == '=' {
+      lexemes.push(Spanned::new(Lexeme::Assign, span));
+    }
+    // Statement terminator
+    else if next == ';'
+      || (next == '\n' && newline_terminates && open_wrappings == 0)
+    {
+      lexemes.push(Spanned::new(Lexeme::Terminator, span));
     }
     // Whitespace
     else if next.is_whitespace() {
@@ -110,16 +304,9 @@ pub fn lex(input: String) -> Vec<Lexeme> {
     }
     // Unrecognized char
     else {
-      lexemes.push(Lexeme::Unknown(next));
+      lexemes.push(Spanned::new(Lexeme::Unknown(next), span));
     }
     char_stream.next();
   }
   lexemes
 }
-
-impl Lexeme {
-  const SPECIAL_CHARS: [char; 19] = [
-    '~', '`', '!', '@', '#', '$', '%', '^', '&', '*', '-', '+', '=', '|', ':',
-    '/', '?', '<', '>',
-  ];
-}
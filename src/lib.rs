@@ -1,22 +1,44 @@
-use error::MathResult;
+use error::{MathError, MathResult};
 
 use crate::{
-  context::Context, number::result_to_string, operator::OperatorSet, parse::Ast,
+  context::Context,
+  number::{result_to_string, result_to_string_in, Base, Number, Numeric},
+  operator::OperatorSet,
+  parse::{Ast, Program, Statement},
 };
 
+pub mod bytecode;
 pub mod context;
+pub mod decimal;
 pub mod error;
+pub mod expression;
 pub mod functions;
+pub mod latex;
 pub mod lexer;
+pub mod mathml;
+mod notation;
 pub mod number;
 pub mod operator;
 pub mod parse;
+pub mod rational;
+pub mod real;
+pub mod span;
 pub mod token;
+pub mod unicode;
 pub mod variables;
+pub mod visit;
 
+/// Snaps floating point noise like `2.9999999` to the nearest integer. Only
+/// noise as small as that left by `sin(100pi)` snaps to zero, so `1e-9`
+/// stays as it is
 fn round(mut num: f64) -> f64 {
-  if (num.round() - num).abs() < 0.0001 {
-    num = num.round()
+  let nearest = num.round();
+  let tolerance = match nearest == 0.0 {
+    true => 1e-10,
+    false => 0.0001,
+  };
+  if (nearest - num).abs() < tolerance {
+    num = nearest
   }
   if num == -0.0 {
     num = 0.0;
@@ -24,27 +46,86 @@ fn round(mut num: f64) -> f64 {
   num
 }
 
+fn round_output(mut num: Number) -> Number {
+  num.re = round(num.re);
+  num.im = round(num.im);
+  num
+}
+
 pub fn evaluate(input: String) -> MathResult {
-  let ls = lexer::lex(input);
-  let ts = token::tokenize(OperatorSet::default(), ls)?;
+  let op_set = OperatorSet::default();
+  let ls = lexer::lex(input, &op_set);
+  let ts = token::tokenize(&op_set, ls)?;
   let context = Context::default();
-  let tree = Ast::new(ts);
+  let tree = Ast::new(ts)?;
   let result = tree.evaluate(&context);
 
   match result {
-    Ok(mut num) => {
-      num.re = round(num.re);
-      num.im = round(num.im);
-      Ok(num)
-    },
+    Ok(num) => Ok(round_output(num)),
     Err(err) => Err(err),
   }
 }
 
+/// Like [evaluate], with any [Numeric] backend, as in
+/// `evaluate_as::<f64>(input)` for real numbers only. Results are as the
+/// backend leaves them, without the rounding [evaluate] does
+pub fn evaluate_as<N: Numeric>(input: String) -> MathResult<N> {
+  let op_set = OperatorSet::<N>::standard();
+  let ls = lexer::lex(input, &op_set);
+  let ts = token::tokenize(&op_set, ls)?;
+  Ast::new(ts)?.evaluate(&Context::with_builtins())
+}
+
+/// Like [evaluate], for input written in LaTeX
+pub fn evaluate_latex(input: String) -> MathResult {
+  let op_set = OperatorSet::default();
+  let ls = latex::lex(input, &op_set);
+  let ts = token::tokenize(&op_set, ls)?;
+  let tree = Ast::new(ts)?;
+  Ok(round_output(tree.evaluate(&Context::default())?))
+}
+
+/// Runs a statement against `context`, so assignments like `x = 3` and
+/// definitions like `f(x) = x^2 + 1` apply to later input. Function
+/// definitions have no value
+pub fn evaluate_in(
+  input: String,
+  context: &mut Context,
+) -> Result<Option<Number>, MathError> {
+  let op_set = OperatorSet::default();
+  let ls = lexer::lex(input, &op_set);
+  let ts = token::tokenize(&op_set, ls)?;
+  let statement = Statement::new(ts)?;
+  Ok(statement.execute(context)?.map(round_output))
+}
+
+/// Runs a whole worksheet like `r = 2; area = pi r^2; area * 3`, returning
+/// the result of each statement in order
+pub fn evaluate_program(
+  input: String,
+  context: &mut Context,
+) -> Result<Vec<Option<Number>>, MathError> {
+  let op_set = OperatorSet::default();
+  let ls = lexer::lex_program(input, &op_set);
+  let ts = token::tokenize(&op_set, ls)?;
+  let program = Program::new(ts)?;
+  Ok(
+    program
+      .execute(context)?
+      .into_iter()
+      .map(|result| result.map(round_output))
+      .collect(),
+  )
+}
+
 pub fn evaluate_to_string(input: String) -> String {
   result_to_string(evaluate(input))
 }
 
+pub fn evaluate_to_string_in(input: String, base: Base) -> String {
+  result_to_string_in(evaluate(input), base)
+}
+
 // fn main() {
 //   let input = "asf(0)".to_string();
 //   println!("input: {}", input);
@@ -55,7 +136,7 @@ pub fn evaluate_to_string(input: String) -> String {
 //
 //   let context = Context::default();
 //
-//   let tree = Ast::new(ts);
+//   let tree = Ast::new(ts)?;
 //   let result = tree.evaluate(&context);
 //   println!("{}", result_to_string(result));
 // }
@@ -0,0 +1,310 @@
+use crate::{
+  context::Context,
+  latex::GREEK_LETTERS,
+  lexer::Wrapping,
+  notation::{self, Layout},
+  number::Number,
+  parse::{Ast, AstNode, Fixity},
+};
+
+const NAMESPACE: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// Invisible operators, so readers know how adjacent symbols relate
+const APPLY_FUNCTION: &str = "<mo>&#x2061;</mo>";
+const INVISIBLE_TIMES: &str = "<mo>&#x2062;</mo>";
+
+/// Built in functions with a content MathML element of their own
+const CONTENT_FUNCTIONS: [&str; 14] = [
+  "sin", "cos", "tan", "csc", "sec", "cot", "arcsin", "arccos", "arctan", "ln",
+  "exp", "abs", "max", "min",
+];
+
+impl Ast {
+  /// Renders how the expression looks, for display and screen readers.
+  /// Custom functions use the names registered in `ctx`
+  pub fn to_presentation_mathml(&self, ctx: &Context) -> String {
+    format!(
+      "<math xmlns=\"{NAMESPACE}\">{}</math>",
+      presentation(&self.root, ctx)
+    )
+  }
+
+  /// Renders what the expression means. Custom functions are applied as
+  /// the heads registered in `ctx`
+  pub fn to_content_mathml(&self, ctx: &Context) -> String {
+    format!(
+      "<math xmlns=\"{NAMESPACE}\">{}</math>",
+      content(&self.root, ctx)
+    )
+  }
+}
+
+fn mrow(children: &[&str]) -> String {
+  format!("<mrow>{}</mrow>", children.concat())
+}
+
+fn parenthesized(inner: &str) -> String {
+  mrow(&["<mo>(</mo>", inner, "<mo>)</mo>"])
+}
+
+fn bars(inner: &str) -> String {
+  mrow(&["<mo>|</mo>", inner, "<mo>|</mo>"])
+}
+
+fn number_presentation(value: Number) -> String {
+  let imaginary = match value.im.abs() {
+    1.0 => "<mi>i</mi>".to_string(),
+    im => mrow(&[&format!("<mn>{im}</mn>"), INVISIBLE_TIMES, "<mi>i</mi>"]),
+  };
+  let sign = match value.im < 0.0 {
+    true => "<mo>\u{2212}</mo>",
+    false => "<mo>+</mo>",
+  };
+  match (value.re, value.im) {
+    (re, 0.0) if re < 0.0 => {
+      mrow(&["<mo>\u{2212}</mo>", &format!("<mn>{}</mn>", -re)])
+    },
+    (re, 0.0) => format!("<mn>{re}</mn>"),
+    (0.0, im) if im < 0.0 => mrow(&[sign, &imaginary]),
+    (0.0, _) => imaginary,
+    (re, _) => mrow(&[&format!("<mn>{re}</mn>"), sign, &imaginary]),
+  }
+}
+
+fn variable_presentation(name: &str) -> String {
+  match GREEK_LETTERS.iter().position(|letter| *letter == name) {
+    Some(index) => {
+      // The alphabet skips the final sigma
+      let offset = if index >= 17 { index + 1 } else { index };
+      let letter = char::from_u32(0x3B1 + offset as u32).unwrap();
+      format!("<mi>{letter}</mi>")
+    },
+    None => format!("<mi>{name}</mi>"),
+  }
+}
+
+fn function_presentation(name: &str, ctx: &Context) -> String {
+  match (ctx.mathml_name(name), name) {
+    (Some((presentation, _)), _) => presentation.to_string(),
+    (None, "gamma") => "<mi mathvariant=\"normal\">\u{393}</mi>".to_string(),
+    (None, name) => format!("<mi>{name}</mi>"),
+  }
+}
+
+fn wrapped_presentation(node: &AstNode, ctx: &Context, wrap: bool) -> String {
+  match wrap {
+    true => parenthesized(&presentation(node, ctx)),
+    false => presentation(node, ctx),
+  }
+}
+
+/// Every node renders as exactly one element, so results can be used as
+/// children of `<mfrac>` and `<msup>` directly
+fn presentation(node: &AstNode, ctx: &Context) -> String {
+  match node {
+    AstNode::Constant { value, .. } => number_presentation(*value),
+    AstNode::Variable { name, .. } => variable_presentation(name),
+    AstNode::Call { name, args, .. } => {
+      let custom = ctx.mathml_name(name).is_some();
+      match (name.as_str(), args.as_slice()) {
+        ("sqrt", [arg]) if !custom => {
+          format!("<msqrt>{}</msqrt>", presentation(arg, ctx))
+        },
+        ("abs", [arg]) if !custom => bars(&presentation(arg, ctx)),
+        ("log", [base, arg]) if !custom => mrow(&[
+          &format!("<msub><mi>log</mi>{}</msub>", presentation(base, ctx)),
+          APPLY_FUNCTION,
+          &parenthesized(&presentation(arg, ctx)),
+        ]),
+        _ => {
+          let args: Vec<_> =
+            args.iter().map(|arg| presentation(arg, ctx)).collect();
+          mrow(&[
+            &function_presentation(name, ctx),
+            APPLY_FUNCTION,
+            &parenthesized(&args.join("<mo>,</mo>")),
+          ])
+        },
+      }
+    },
+    AstNode::Unary {
+      op,
+      fixity: Fixity::Prefix,
+      operand,
+      ..
+    } => match op.symbol {
+      "√" => format!("<msqrt>{}</msqrt>", presentation(operand, ctx)),
+      symbol => {
+        let symbol = match symbol {
+          "-" => "\u{2212}",
+          symbol => symbol,
+        };
+        let wrap = notation::wrap_prefix_operand(Layout::Typeset, op, operand);
+        mrow(&[
+          &format!("<mo>{symbol}</mo>"),
+          &wrapped_presentation(operand, ctx, wrap),
+        ])
+      },
+    },
+    AstNode::Unary {
+      op,
+      fixity: Fixity::Postfix,
+      operand,
+      ..
+    } => {
+      let wrap = notation::wrap_postfix_operand(Layout::Typeset, operand);
+      mrow(&[
+        &wrapped_presentation(operand, ctx, wrap),
+        &format!("<mo>{}</mo>", op.symbol),
+      ])
+    },
+    AstNode::Binary {
+      op, left, right, ..
+    } => match op.symbol {
+      "/" => format!(
+        "<mfrac>{}{}</mfrac>",
+        presentation(left, ctx),
+        presentation(right, ctx)
+      ),
+      "^" => format!(
+        "<msup>{}{}</msup>",
+        wrapped_presentation(left, ctx, notation::wrap_base(left)),
+        presentation(right, ctx)
+      ),
+      symbol => {
+        let symbol = match symbol {
+          "*" => "\u{22C5}",
+          "-" => "\u{2212}",
+          symbol => symbol,
+        };
+        mrow(&[
+          &wrapped_presentation(
+            left,
+            ctx,
+            notation::wrap_left(Layout::Typeset, op, left),
+          ),
+          &format!("<mo>{symbol}</mo>"),
+          &wrapped_presentation(
+            right,
+            ctx,
+            notation::wrap_right(Layout::Typeset, op, right),
+          ),
+        ])
+      },
+    },
+    AstNode::Group {
+      wrapping: Wrapping::Bar,
+      inner,
+      ..
+    } => bars(&presentation(inner, ctx)),
+    AstNode::Group { inner, .. } => presentation(inner, ctx),
+  }
+}
+
+fn apply(head: &str, args: &[String]) -> String {
+  format!("<apply>{head}{}</apply>", args.concat())
+}
+
+fn number_content(value: Number) -> String {
+  match value.im {
+    0.0 => format!("<cn>{}</cn>", value.re),
+    im => format!("<cn type=\"complex-cartesian\">{}<sep/>{im}</cn>", value.re),
+  }
+}
+
+fn variable_content(name: &str) -> String {
+  match name {
+    "pi" => "<pi/>".to_string(),
+    "e" => "<exponentiale/>".to_string(),
+    "i" => "<imaginaryi/>".to_string(),
+    name => format!("<ci>{name}</ci>"),
+  }
+}
+
+fn function_content(name: &str, ctx: &Context) -> String {
+  match ctx.mathml_name(name) {
+    Some((_, content)) => content.to_string(),
+    None if CONTENT_FUNCTIONS.contains(&name) => format!("<{name}/>"),
+    None => format!("<ci type=\"function\">{name}</ci>"),
+  }
+}
+
+fn content(node: &AstNode, ctx: &Context) -> String {
+  match node {
+    AstNode::Constant { value, .. } => number_content(*value),
+    AstNode::Variable { name, .. } => variable_content(name),
+    AstNode::Call { name, args, .. } => {
+      let custom = ctx.mathml_name(name).is_some();
+      match (name.as_str(), args.as_slice()) {
+        ("sqrt", [arg]) if !custom => apply("<root/>", &[content(arg, ctx)]),
+        ("log", [base, arg]) if !custom => apply(
+          "<log/>",
+          &[
+            format!("<logbase>{}</logbase>", content(base, ctx)),
+            content(arg, ctx),
+          ],
+        ),
+        _ => {
+          let args: Vec<_> = args.iter().map(|arg| content(arg, ctx)).collect();
+          apply(&function_content(name, ctx), &args)
+        },
+      }
+    },
+    AstNode::Unary {
+      op,
+      fixity: Fixity::Prefix,
+      operand,
+      ..
+    } => {
+      let head = match op.symbol {
+        "-" => "<minus/>".to_string(),
+        "√" => "<root/>".to_string(),
+        _ => format!("<csymbol>{}</csymbol>", op.name),
+      };
+      apply(&head, &[content(operand, ctx)])
+    },
+    AstNode::Unary {
+      op,
+      fixity: Fixity::Postfix,
+      operand,
+      ..
+    } => match op.symbol {
+      "!" => apply("<factorial/>", &[content(operand, ctx)]),
+      "%" => apply(
+        "<divide/>",
+        &[content(operand, ctx), "<cn>100</cn>".to_string()],
+      ),
+      _ => apply(
+        &format!("<csymbol>{}</csymbol>", op.name),
+        &[content(operand, ctx)],
+      ),
+    },
+    AstNode::Binary {
+      op, left, right, ..
+    } => {
+      let head = match op.symbol {
+        "+" => "<plus/>".to_string(),
+        "-" => "<minus/>".to_string(),
+        "*" => "<times/>".to_string(),
+        "/" => "<divide/>".to_string(),
+        "^" => "<power/>".to_string(),
+        _ => format!("<csymbol>{}</csymbol>", op.name),
+      };
+      let left_content = content(left, ctx);
+      let right_content = match right.is_relative()
+        && op.relative_func.is_some()
+      {
+        // `a + b%` means `a + a * b%`
+        true => apply("<times/>", &[left_content.clone(), content(right, ctx)]),
+        false => content(right, ctx),
+      };
+      apply(&head, &[left_content, right_content])
+    },
+    AstNode::Group {
+      wrapping: Wrapping::Bar,
+      inner,
+      ..
+    } => apply("<abs/>", &[content(inner, ctx)]),
+    AstNode::Group { inner, .. } => content(inner, ctx),
+  }
+}
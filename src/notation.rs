@@ -0,0 +1,125 @@
+//! Where printed expressions need parentheses. Plain infix writes every
+//! operator between its operands, while typeset output like LaTeX and MathML
+//! draws fractions and powers, so they group on their own
+
+use crate::{
+  lexer::Wrapping,
+  number::Numeric,
+  operator::{Associativity, Operation},
+  parse::{AstNode, Fixity},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Layout {
+  /// Plain text, as from [Display](std::fmt::Display)
+  Inline,
+  /// LaTeX and MathML
+  Typeset,
+}
+
+/// Precedence of the node when it's written next to an operator, or `None`
+/// for anything that can't be split up, like a function call or a typeset
+/// fraction
+fn precedence<N: Numeric>(node: &AstNode<N>, layout: Layout) -> Option<usize> {
+  match (node.unwrapped(), layout) {
+    (AstNode::Binary { op, .. }, Layout::Typeset) if op.symbol == "/" => None,
+    (AstNode::Binary { op, .. }, _) => Some(op.precedence),
+    // Written as a sum
+    (AstNode::Constant { value, .. }, Layout::Typeset) => {
+      let value = value.to_number();
+      (value.re != 0.0 && value.im != 0.0).then_some(0)
+    },
+    _ => None,
+  }
+}
+
+fn is_prefix<N: Numeric>(node: &AstNode<N>) -> bool {
+  matches!(
+    node.unwrapped(),
+    AstNode::Unary {
+      fixity: Fixity::Prefix,
+      ..
+    }
+  )
+}
+
+/// Roots are drawn around their operand, so they never need wrapping
+fn is_root<N: Numeric>(node: &AstNode<N>) -> bool {
+  matches!(node.unwrapped(), AstNode::Unary { op, .. } if op.symbol == "√")
+}
+
+pub(crate) fn wrap_prefix_operand<N: Numeric>(
+  layout: Layout,
+  op: &Operation<N>,
+  operand: &AstNode<N>,
+) -> bool {
+  precedence(operand, layout).is_some_and(|p| p < op.unary_precedence)
+}
+
+pub(crate) fn wrap_postfix_operand<N: Numeric>(
+  layout: Layout,
+  operand: &AstNode<N>,
+) -> bool {
+  precedence(operand, layout).is_some()
+    || is_prefix(operand)
+    || matches!(operand.unwrapped(), AstNode::Binary { .. })
+}
+
+/// Anything but a single symbol needs wrapping to be raised to a power
+pub(crate) fn wrap_base<N: Numeric>(base: &AstNode<N>) -> bool {
+  match base.unwrapped() {
+    AstNode::Constant { value, .. } => value.to_number().im != 0.0,
+    AstNode::Variable { .. }
+    | AstNode::Call { .. }
+    | AstNode::Group {
+      wrapping: Wrapping::Bar,
+      ..
+    } => false,
+    _ => true,
+  }
+}
+
+/// For operators written inline, between their operands
+pub(crate) fn wrap_left<N: Numeric>(
+  layout: Layout,
+  op: &Operation<N>,
+  left: &AstNode<N>,
+) -> bool {
+  match (precedence(left, layout), left.unwrapped()) {
+    (Some(p), _) => {
+      p < op.precedence
+        || (p == op.precedence && op.associativity == Associativity::Right)
+    },
+    // A prefix operator would take the whole operation as its operand, as in
+    // `-2^2`
+    (
+      None,
+      AstNode::Unary {
+        op: prefix,
+        fixity: Fixity::Prefix,
+        ..
+      },
+    ) => op.precedence >= prefix.unary_precedence,
+    (None, _) => false,
+  }
+}
+
+/// For operators written inline, between their operands
+pub(crate) fn wrap_right<N: Numeric>(
+  layout: Layout,
+  op: &Operation<N>,
+  right: &AstNode<N>,
+) -> bool {
+  match precedence(right, layout) {
+    Some(p) => {
+      p < op.precedence
+        || (p == op.precedence && op.associativity == Associativity::Left)
+    },
+    // Grouping stops `200 + (10%)` from being relative to the `200`
+    None if right.unwrapped().is_relative() => {
+      op.relative_func.is_some() && !right.is_relative()
+    },
+    // Keeps typeset `a - -b` from running two signs together
+    None => layout == Layout::Typeset && is_prefix(right) && !is_root(right),
+  }
+}
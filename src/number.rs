@@ -1,40 +1,371 @@
-use num::Complex;
+use std::{
+  cmp::Ordering,
+  f64::consts::{E, PI, TAU},
+  fmt::{Debug, Display},
+  ops::{Add, Div, Mul, Neg, Sub},
+};
 
-use crate::error::{MathError, MathResult};
+use num::{BigInt, Complex, FromPrimitive, Num, One, ToPrimitive, Zero};
+
+use crate::{
+  error::{MathError, MathResult},
+  span::Span,
+};
 
 pub type Number = Complex<f64>;
 
-pub fn round_num(mut num: Number) -> Number {
-  if num.re.fract().abs() < f64::EPSILON {
-    num.re = num.re.trunc();
+/// Arithmetic the engine evaluates with. [Number] is the default, other
+/// implementations change what kind of numbers expressions work on.
+///
+/// Anything without a method of its own here goes through [Number] with
+/// [Numeric::to_number] and [Numeric::from_number], so a backend only has to
+/// implement what it can do better than complex floating point
+pub trait Numeric:
+  Clone
+  + Debug
+  + Display
+  + PartialEq
+  + Zero
+  + One
+  + FromPrimitive
+  + Add<Output = Self>
+  + Sub<Output = Self>
+  + Mul<Output = Self>
+  + Div<Output = Self>
+  + Neg<Output = Self>
+{
+  /// Parses the text of a number lexeme, see [parse_literal]
+  fn parse_literal(literal: &str) -> Option<Self>;
+
+  fn to_number(&self) -> Number;
+
+  /// Fails for values the backend can't represent
+  fn from_number(num: Number) -> MathResult<Self>;
+
+  /// Rejects results that aren't numbers and cleans up rounding error
+  fn sanitize(self) -> MathResult<Self>;
+
+  /// Writes a result for display
+  fn format(&self) -> String {
+    self.to_string()
+  }
+
+  /// Writes the number so it reads back as the same value inside an
+  /// expression
+  fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.format())
+  }
+
+  /// Value of a built in constant like `pi`, if the backend has it
+  fn constant(name: &str) -> Option<Self> {
+    let value = match name {
+      "pi" => PI.into(),
+      "e" => E.into(),
+      "tau" => TAU.into(),
+      "i" => Number::i(),
+      _ => return None,
+    };
+    Self::from_number(value).ok()
+  }
+
+  /// Orders real numbers, [None] if either isn't one
+  fn real_cmp(&self, other: &Self) -> Option<Ordering> {
+    let (a, b) = (self.to_number(), other.to_number());
+    match (a.im, b.im) {
+      (0.0, 0.0) => a.re.partial_cmp(&b.re),
+      _ => None,
+    }
+  }
+
+  fn abs(self) -> Self;
+
+  fn pow(self, exponent: Self) -> MathResult<Self> {
+    let (a, b) = (self.to_number(), exponent.to_number());
+    // Stay on the real line when possible, complex powers lose precision
+    if a.im == 0.0 && b.im == 0.0 && (a.re >= 0.0 || b.re.fract() == 0.0) {
+      return Self::from_number(sanitize_result(a.re.powf(b.re).into())?);
+    }
+    Self::from_number(sanitize_result(a.powc(b))?)
+  }
+
+  fn sqrt(self) -> MathResult<Self> {
+    lift(self, |n| n.sqrt())
+  }
+
+  fn factorial(self) -> MathResult<Self> {
+    Self::from_number(factorial(self.to_number())?)
+  }
+
+  fn gamma(self) -> MathResult<Self> {
+    Self::from_number(gamma(self.to_number())?)
+  }
+
+  fn exp(self) -> MathResult<Self> {
+    lift(self, |n| n.exp())
+  }
+
+  fn ln(self) -> MathResult<Self> {
+    lift(self, |n| n.ln())
+  }
+
+  fn sin(self) -> MathResult<Self> {
+    lift(self, |n| n.sin())
+  }
+
+  fn cos(self) -> MathResult<Self> {
+    lift(self, |n| n.cos())
+  }
+
+  fn tan(self) -> MathResult<Self> {
+    lift(self, |n| n.tan())
+  }
+
+  fn asin(self) -> MathResult<Self> {
+    lift(self, |n| n.asin())
+  }
+
+  fn acos(self) -> MathResult<Self> {
+    lift(self, |n| n.acos())
+  }
+
+  fn atan(self) -> MathResult<Self> {
+    lift(self, |n| n.atan())
+  }
+
+  /// Angle of the point `(x, self)`, only defined for real numbers
+  fn atan2(self, x: Self) -> MathResult<Self> {
+    match (self.to_number(), x.to_number()) {
+      (y, x) if y.im == 0.0 && x.im == 0.0 => {
+        Self::from_number(sanitize_result(y.re.atan2(x.re).into())?)
+      },
+      _ => Err(MathError::UndefinedOperation(
+        "complex atan2".to_string(),
+        Span::default(),
+      )),
+    }
+  }
+}
+
+/// Applies `func` to the [Number] closest to `num`
+fn lift<N: Numeric>(num: N, func: impl Fn(Number) -> Number) -> MathResult<N> {
+  N::from_number(sanitize_result(func(num.to_number()))?)
+}
+
+impl Numeric for Number {
+  fn parse_literal(literal: &str) -> Option<Self> {
+    parse_literal(literal)
+  }
+
+  fn to_number(&self) -> Number {
+    *self
+  }
+
+  fn from_number(num: Number) -> MathResult<Self> {
+    Ok(num)
+  }
+
+  fn sanitize(self) -> MathResult<Self> {
+    sanitize_result(self)
+  }
+
+  fn format(&self) -> String {
+    if self.im == 0.0 {
+      self.re.to_string()
+    } else if self.re != 0.0 && self.im != 0.0 {
+      self.to_string()
+    } else {
+      format!("{}i", self.im)
+    }
+  }
+
+  fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match (self.re, self.im) {
+      (re, 0.0) => write!(f, "{re}"),
+      (0.0, im) => write!(f, "{im}i"),
+      (re, im) if im < 0.0 => write!(f, "({re} - {}i)", -im),
+      (re, im) => write!(f, "({re} + {im}i)"),
+    }
+  }
+
+  fn abs(self) -> Self {
+    self.norm().into()
+  }
+}
+
+/// Base to write integer results in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Base {
+  Binary,
+  Octal,
+  #[default]
+  Decimal,
+  Hexadecimal,
+}
+
+impl Base {
+  pub const fn radix(&self) -> u32 {
+    match self {
+      Base::Binary => 2,
+      Base::Octal => 8,
+      Base::Decimal => 10,
+      Base::Hexadecimal => 16,
+    }
+  }
+
+  pub const fn prefix(&self) -> &'static str {
+    match self {
+      Base::Binary => "0b",
+      Base::Octal => "0o",
+      Base::Decimal => "",
+      Base::Hexadecimal => "0x",
+    }
+  }
+
+  /// Matches the second char of a `0x`, `0b` or `0o` prefix
+  pub const fn from_marker(marker: char) -> Option<Base> {
+    Some(match marker {
+      'b' | 'B' => Base::Binary,
+      'o' | 'O' => Base::Octal,
+      'x' | 'X' => Base::Hexadecimal,
+      _ => return None,
+    })
   }
-  if num.im.fract().abs() < f64::EPSILON {
-    num.im = num.im.trunc();
+}
+
+/// Parses the text of a number lexeme, including radix prefixed integers and
+/// imaginary literals. Underscores between digits are ignored, as in `1_000`
+pub fn parse_literal(literal: &str) -> Option<Number> {
+  let literal: &str = &literal.replace('_', "");
+  let mut chars = literal.chars();
+  if let (Some('0'), Some(marker)) = (chars.next(), chars.next()) {
+    if let Some(base) = Base::from_marker(marker) {
+      // Wider than u64, so long hex literals round like decimal ones do
+      let int = BigInt::from_str_radix(&literal[2..], base.radix()).ok()?;
+      return Some(Number::new(int.to_f64()?, 0.0));
+    }
+  }
+  // Imaginary literals like `3i` or `2.5j`
+  if let Some(imaginary) = literal.strip_suffix(['i', 'j']) {
+    let im = imaginary.parse::<f64>().ok()?;
+    return Some(Number::new(0.0, im));
+  }
+  literal.parse::<Number>().ok()
+}
+
+// Lanczos approximation coefficients for g = 7
+const LANCZOS_G: f64 = 7.0;
+const LANCZOS_COEF: [f64; 9] = [
+  0.999_999_999_999_809_9,
+  676.520_368_121_885_1,
+  -1_259.139_216_722_402_8,
+  771.323_428_777_653_1,
+  -176.615_029_162_140_6,
+  12.507_343_278_686_905,
+  -0.138_571_095_265_720_12,
+  9.984_369_578_019_572e-6,
+  1.505_632_735_149_311_6e-7,
+];
+
+/// Gamma function over the complex plane, undefined at the poles on the
+/// non-positive integers
+pub fn gamma(z: Number) -> MathResult {
+  if z.im == 0.0 && z.re <= 0.0 && z.re.fract() == 0.0 {
+    return Err(MathError::Undefined(Span::default()));
+  }
+  // Reflection formula keeps the approximation in the right half plane
+  if z.re < 0.5 {
+    let pi = Number::new(PI, 0.0);
+    return sanitize_result(pi / ((pi * z).sin() * gamma(1.0 - z)?));
+  }
+  let z = z - 1.0;
+  let mut sum = Number::new(LANCZOS_COEF[0], 0.0);
+  for (i, coef) in LANCZOS_COEF.iter().enumerate().skip(1) {
+    sum += coef / (z + i as f64);
+  }
+  let t = z + LANCZOS_G + 0.5;
+  sanitize_result((2.0 * PI).sqrt() * t.powc(z + 0.5) * (-t).exp() * sum)
+}
+
+/// Factorial, exact for non-negative integers and extended to every other
+/// number through `gamma(n + 1)`
+pub fn factorial(num: Number) -> MathResult {
+  if num.im == 0.0 && num.re >= 0.0 && num.re.fract() == 0.0 {
+    // Anything past 170! overflows anyway
+    if num.re > 170.0 {
+      return Err(MathError::TooBig(Span::default()));
+    }
+    let product = (1..=num.re as u64).fold(1.0, |acc, n| acc * n as f64);
+    return sanitize_result(product.into());
+  }
+  gamma(num + 1.0)
+}
+
+/// Snaps values within an epsilon of an integer onto it
+fn round_part(part: f64) -> f64 {
+  match part.fract().abs() < f64::EPSILON {
+    true => part.trunc(),
+    false => part,
+  }
+}
+
+pub fn round_num(mut num: Number) -> Number {
+  num.re = round_part(num.re);
+  num.im = round_part(num.im);
+  // Negative zero would put real numbers on the wrong side of the branch cut
+  if num.im == 0.0 {
+    num.im = 0.0;
   }
   num
 }
 
 pub fn sanitize_result(num: Number) -> MathResult {
   if num.is_nan() {
-    Err(MathError::Undefined)
+    Err(MathError::Undefined(Span::default()))
   } else if num.is_infinite() {
-    Err(MathError::TooBig)
+    Err(MathError::TooBig(Span::default()))
   } else {
     Ok(round_num(num))
   }
 }
 
-pub fn result_to_string(result: MathResult) -> String {
+/// [sanitize_result] for real numbers
+pub fn sanitize_real(num: f64) -> Result<f64, MathError> {
+  if num.is_nan() {
+    Err(MathError::Undefined(Span::default()))
+  } else if num.is_infinite() {
+    Err(MathError::TooBig(Span::default()))
+  } else {
+    Ok(round_part(num))
+  }
+}
+
+pub fn result_to_string<N: Numeric>(result: MathResult<N>) -> String {
+  match result {
+    Ok(num) => num.format(),
+    Err(_) => "".to_string(),
+  }
+}
+
+/// Like [result_to_string], but writes real integers in the given base.
+/// Anything else falls back to decimal
+pub fn result_to_string_in(result: MathResult, base: Base) -> String {
   match result {
-    Ok(num) => {
-      if num.im == 0.0 {
-        return num.re.to_string();
-      } else if num.re != 0.0 && num.im != 0.0 {
-        return num.to_string();
-      } else {
-        return format!("{}i", num.im);
-      }
+    Ok(num)
+      if base != Base::Decimal
+        && num.im == 0.0
+        && num.re.fract() == 0.0
+        && num.re.abs() <= u64::MAX as f64 =>
+    {
+      let sign = if num.re < 0.0 { "-" } else { "" };
+      let int = num.re.abs() as u64;
+      let digits = match base {
+        Base::Binary => format!("{int:b}"),
+        Base::Octal => format!("{int:o}"),
+        Base::Hexadecimal => format!("{int:X}"),
+        Base::Decimal => int.to_string(),
+      };
+      format!("{sign}{}{digits}", base.prefix())
     },
-    Err(err) => "".to_string(),
+    result => result_to_string(result),
   }
 }
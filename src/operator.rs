@@ -2,7 +2,8 @@ use std::collections::HashMap;
 
 use crate::{
   error::{MathError, MathResult},
-  number::{sanitize_result, Number},
+  number::{Number, Numeric},
+  span::Span,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -11,144 +12,339 @@ pub enum Associativity {
   Right,
 }
 
-type BinaryOperationFunc = fn(a: Number, b: Number) -> MathResult;
-type UnaryOperationFunc = fn(num: Number) -> MathResult;
+type BinaryOperationFunc<N> = fn(a: N, b: N) -> MathResult<N>;
+type UnaryOperationFunc<N> = fn(num: N) -> MathResult<N>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Operation {
+#[derive(Debug, Clone)]
+pub struct Operation<N = Number> {
   pub name: &'static str,
+  /// Canonical symbol, used when writing expressions back out
+  pub symbol: &'static str,
   pub associativity: Associativity,
   pub precedence: usize,
-  pub binary_func: Option<BinaryOperationFunc>,
-  pub unary_func: Option<UnaryOperationFunc>,
+  /// Precedence when used as a prefix operator, so that `-2^2` is `-4` while
+  /// `-2*3` still negates just the `2`
+  pub unary_precedence: usize,
+  pub binary_func: Option<BinaryOperationFunc<N>>,
+  pub unary_func: Option<UnaryOperationFunc<N>>,
+  pub postfix_func: Option<UnaryOperationFunc<N>>,
+  /// Used instead of `binary_func` when the right operand is relative to the
+  /// left one, as in `200 + 10%`
+  pub relative_func: Option<BinaryOperationFunc<N>>,
+  /// Whether the postfix result is a fraction of whatever it's applied to
+  pub relative: bool,
 }
 
-impl Operation {
-  pub fn perform_binary(&self, a: Number, b: Number) -> MathResult {
+impl<N: Numeric> Operation<N> {
+  pub fn perform_binary(&self, a: N, b: N) -> MathResult<N> {
     match self.binary_func {
-      Some(func) => sanitize_result((func)(a, b)?),
-      None => Err(MathError::UndefinedOperation(format!(
-        "binary {}",
-        self.name
-      ))),
+      Some(func) => (func)(a, b)?.sanitize(),
+      None => Err(MathError::UndefinedOperation(
+        format!("binary {}", self.name),
+        Span::default(),
+      )),
     }
   }
 
-  pub fn perform_unary(&self, num: Number) -> MathResult {
+  pub fn perform_unary(&self, num: N) -> MathResult<N> {
     match self.unary_func {
-      Some(func) => sanitize_result((func)(num)?),
-      None => Err(MathError::UndefinedOperation(format!(
-        "unary {}",
-        self.name
-      ))),
+      Some(func) => (func)(num)?.sanitize(),
+      None => Err(MathError::UndefinedOperation(
+        format!("unary {}", self.name),
+        Span::default(),
+      )),
     }
   }
+
+  pub fn perform_postfix(&self, num: N) -> MathResult<N> {
+    match self.postfix_func {
+      Some(func) => (func)(num)?.sanitize(),
+      None => Err(MathError::UndefinedOperation(
+        format!("postfix {}", self.name),
+        Span::default(),
+      )),
+    }
+  }
+
+  /// Like [Operation::perform_binary], with `b` relative to `a`. Falls back to
+  /// the plain binary operation, so `200 * 10%` is still `20`
+  pub fn perform_relative(&self, a: N, b: N) -> MathResult<N> {
+    match self.relative_func {
+      Some(func) => (func)(a, b)?.sanitize(),
+      None => self.perform_binary(a, b),
+    }
+  }
+
+  /// Operations like `√` that only ever precede their operand
+  pub fn is_prefix_only(&self) -> bool {
+    self.unary_func.is_some()
+      && self.binary_func.is_none()
+      && self.postfix_func.is_none()
+  }
+
+  /// Operations like `!` that only ever follow their operand
+  pub fn is_postfix_only(&self) -> bool {
+    self.postfix_func.is_some()
+      && self.binary_func.is_none()
+      && self.unary_func.is_none()
+  }
 }
 
-impl std::fmt::Display for Operation {
+// Function pointers can't be compared meaningfully, operations are told apart
+// by name instead
+impl<N> PartialEq for Operation<N> {
+  fn eq(&self, other: &Self) -> bool {
+    self.name == other.name
+  }
+}
+
+impl<N> Eq for Operation<N> {}
+
+impl<N> std::fmt::Display for Operation<N> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     write!(f, "{}", self.name)
   }
 }
 
-pub struct OperatorSet {
-  set: HashMap<char, Operation>,
-  implicit: Operation,
+pub struct OperatorSet<N = Number> {
+  set: HashMap<String, Operation<N>>,
+  implicit: Operation<N>,
 }
 
-impl OperatorSet {
-  pub fn new(operators: Vec<(char, Operation)>, implicit: Operation) -> Self {
+impl<N> OperatorSet<N> {
+  pub fn new(
+    operators: Vec<(&str, Operation<N>)>,
+    implicit: Operation<N>,
+  ) -> Self {
     let mut set = HashMap::new();
     for (symbol, op) in operators {
-      set.insert(symbol, op);
+      set.insert(symbol.to_string(), op);
     }
     Self { set, implicit }
   }
 
-  pub fn get(&self, symbol: &char) -> Option<&Operation> {
+  pub fn get(&self, symbol: &str) -> Option<&Operation<N>> {
     self.set.get(symbol)
   }
 
-  pub fn implicit(&self) -> &Operation {
+  /// Finds the longest registered symbol that `input` starts with
+  pub fn match_symbol(&self, input: &str) -> Option<&str> {
+    self
+      .set
+      .keys()
+      .filter(|symbol| input.starts_with(symbol.as_str()))
+      .max_by_key(|symbol| symbol.len())
+      .map(|symbol| symbol.as_str())
+  }
+
+  pub fn implicit(&self) -> &Operation<N> {
     &self.implicit
   }
 }
 
-impl Default for OperatorSet {
-  fn default() -> Self {
+impl<N: Numeric> OperatorSet<N> {
+  /// The usual operators for any backend, as in
+  /// `OperatorSet::<f64>::standard()`. [OperatorSet::default] is this for the
+  /// default backend
+  pub fn standard() -> Self {
     Self::new(
       vec![
-        ('+', OP_ADD),
-        ('-', OP_SUB),
-        ('*', OP_MULT),
-        ('/', OP_DIV),
-        ('^', OP_EXP),
+        ("+", Operation::addition()),
+        ("-", Operation::subtraction()),
+        ("*", Operation::multiplication()),
+        ("/", Operation::division()),
+        ("^", Operation::exponentiation()),
+        ("**", Operation::exponentiation()),
+        ("√", Operation::square_root()),
+        ("!", Operation::factorial()),
+        ("%", Operation::percent()),
       ],
-      OP_MULT,
+      Operation::multiplication(),
     )
   }
 }
 
-fn _op_add(a: Number, b: Number) -> MathResult {
+impl Default for OperatorSet {
+  fn default() -> Self {
+    Self::standard()
+  }
+}
+
+fn _op_add<N: Numeric>(a: N, b: N) -> MathResult<N> {
   Ok(a + b)
 }
 
-fn _op_sub(a: Number, b: Number) -> MathResult {
+fn _op_sub<N: Numeric>(a: N, b: N) -> MathResult<N> {
   Ok(a - b)
 }
 
-fn _op_sub_unary(num: Number) -> MathResult {
+fn _op_add_relative<N: Numeric>(a: N, b: N) -> MathResult<N> {
+  Ok(a.clone() + a * b)
+}
+
+fn _op_sub_relative<N: Numeric>(a: N, b: N) -> MathResult<N> {
+  Ok(a.clone() - a * b)
+}
+
+fn _op_sub_unary<N: Numeric>(num: N) -> MathResult<N> {
   Ok(-num)
 }
 
-fn _op_mult(a: Number, b: Number) -> MathResult {
+fn _op_mult<N: Numeric>(a: N, b: N) -> MathResult<N> {
   Ok(a * b)
 }
 
-fn _op_div(a: Number, b: Number) -> MathResult {
+fn _op_div<N: Numeric>(a: N, b: N) -> MathResult<N> {
+  if b.is_zero() {
+    return Err(MathError::DivisionByZero(Span::default()));
+  }
   Ok(a / b)
 }
 
-fn _op_exp(a: Number, b: Number) -> MathResult {
-  Ok(a.powc(b))
+fn _op_exp<N: Numeric>(a: N, b: N) -> MathResult<N> {
+  a.pow(b)
 }
 
-pub const OP_ADD: Operation = Operation {
-  name: "addition",
-  associativity: Associativity::Left,
-  precedence: 0,
-  binary_func: Some(_op_add),
-  unary_func: None,
-};
+fn _op_sqrt_unary<N: Numeric>(num: N) -> MathResult<N> {
+  num.sqrt()
+}
 
-pub const OP_SUB: Operation = Operation {
-  name: "subtraction",
-  associativity: Associativity::Left,
-  precedence: 0,
-  binary_func: Some(_op_sub),
-  unary_func: Some(_op_sub_unary),
-};
+fn _op_fact_postfix<N: Numeric>(num: N) -> MathResult<N> {
+  num.factorial()
+}
 
-pub const OP_MULT: Operation = Operation {
-  name: "multiplication",
-  associativity: Associativity::Left,
-  precedence: 1,
-  binary_func: Some(_op_mult),
-  unary_func: None,
-};
+fn _op_percent_postfix<N: Numeric>(num: N) -> MathResult<N> {
+  Ok(num / N::from_u8(100).unwrap())
+}
 
-pub const OP_DIV: Operation = Operation {
-  name: "division",
-  associativity: Associativity::Left,
-  precedence: 1,
-  binary_func: Some(_op_div),
-  unary_func: None,
-};
+/// The built in operations, for any [Numeric] backend
+impl<N: Numeric> Operation<N> {
+  pub const fn addition() -> Self {
+    Operation {
+      name: "addition",
+      symbol: "+",
+      associativity: Associativity::Left,
+      precedence: 0,
+      unary_precedence: 0,
+      binary_func: Some(_op_add),
+      unary_func: None,
+      postfix_func: None,
+      relative_func: Some(_op_add_relative),
+      relative: false,
+    }
+  }
 
-pub const OP_EXP: Operation = Operation {
-  name: "exponentiation",
-  associativity: Associativity::Right,
-  precedence: 2,
-  binary_func: Some(_op_exp),
-  unary_func: None,
-};
+  pub const fn subtraction() -> Self {
+    Operation {
+      name: "subtraction",
+      symbol: "-",
+      associativity: Associativity::Left,
+      precedence: 0,
+      unary_precedence: 2,
+      binary_func: Some(_op_sub),
+      unary_func: Some(_op_sub_unary),
+      postfix_func: None,
+      relative_func: Some(_op_sub_relative),
+      relative: false,
+    }
+  }
+
+  pub const fn multiplication() -> Self {
+    Operation {
+      name: "multiplication",
+      symbol: "*",
+      associativity: Associativity::Left,
+      precedence: 1,
+      unary_precedence: 1,
+      binary_func: Some(_op_mult),
+      unary_func: None,
+      postfix_func: None,
+      relative_func: None,
+      relative: false,
+    }
+  }
+
+  pub const fn division() -> Self {
+    Operation {
+      name: "division",
+      symbol: "/",
+      associativity: Associativity::Left,
+      precedence: 1,
+      unary_precedence: 1,
+      binary_func: Some(_op_div),
+      unary_func: None,
+      postfix_func: None,
+      relative_func: None,
+      relative: false,
+    }
+  }
+
+  pub const fn exponentiation() -> Self {
+    Operation {
+      name: "exponentiation",
+      symbol: "^",
+      associativity: Associativity::Right,
+      precedence: 3,
+      unary_precedence: 3,
+      binary_func: Some(_op_exp),
+      unary_func: None,
+      postfix_func: None,
+      relative_func: None,
+      relative: false,
+    }
+  }
+
+  pub const fn square_root() -> Self {
+    Operation {
+      name: "square root",
+      symbol: "√",
+      associativity: Associativity::Right,
+      precedence: 2,
+      unary_precedence: 2,
+      binary_func: None,
+      unary_func: Some(_op_sqrt_unary),
+      postfix_func: None,
+      relative_func: None,
+      relative: false,
+    }
+  }
+
+  pub const fn factorial() -> Self {
+    Operation {
+      name: "factorial",
+      symbol: "!",
+      associativity: Associativity::Left,
+      precedence: 5,
+      unary_precedence: 5,
+      binary_func: None,
+      unary_func: None,
+      postfix_func: Some(_op_fact_postfix),
+      relative_func: None,
+      relative: false,
+    }
+  }
+
+  pub const fn percent() -> Self {
+    Operation {
+      name: "percent",
+      symbol: "%",
+      associativity: Associativity::Left,
+      precedence: 5,
+      unary_precedence: 5,
+      binary_func: None,
+      unary_func: None,
+      postfix_func: Some(_op_percent_postfix),
+      relative_func: None,
+      relative: true,
+    }
+  }
+}
+
+pub const OP_ADD: Operation = Operation::addition();
+pub const OP_SUB: Operation = Operation::subtraction();
+pub const OP_MULT: Operation = Operation::multiplication();
+pub const OP_DIV: Operation = Operation::division();
+pub const OP_EXP: Operation = Operation::exponentiation();
+pub const OP_SQRT: Operation = Operation::square_root();
+pub const OP_FACT: Operation = Operation::factorial();
+pub const OP_PERCENT: Operation = Operation::percent();
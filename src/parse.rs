@@ -1,206 +1,776 @@
-use std::cmp::Ordering;
-
 use crate::{
   context::Context,
   error::{MathError, MathResult},
-  operator::{Associativity, Operation},
+  functions::Function,
+  lexer::Wrapping,
+  notation::{self, Layout},
+  number::{Number, Numeric},
+  operator::{Associativity, Operation, OP_SUB},
+  span::{Span, Spanned},
   token::Token,
+  visit::Visitor,
 };
 
+/// Bare function calls like `sin x^2` take their argument at the same
+/// precedence as prefix minus, so they read as `sin(x^2)`. Implicit
+/// multiplication carries the argument on, see [Parser::parse_bare_argument]
+const BARE_CALL_PRECEDENCE: usize = OP_SUB.unary_precedence;
+
+/// Whether a unary operator comes before or after its operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixity {
+  Prefix,
+  Postfix,
+}
+
 #[derive(Clone)]
-pub enum AstNode {
-  Some {
-    token: Token,
-    left: Box<AstNode>,
-    right: Box<AstNode>,
+pub enum AstNode<N: Numeric = Number> {
+  Constant {
+    value: N,
+    span: Span,
+  },
+  Variable {
+    name: String,
+    span: Span,
+  },
+  Call {
+    name: String,
+    args: Vec<AstNode<N>>,
+    span: Span,
+  },
+  Unary {
+    op: Operation<N>,
+    fixity: Fixity,
+    operand: Box<AstNode<N>>,
+    span: Span,
+  },
+  Binary {
+    op: Operation<N>,
+    left: Box<AstNode<N>>,
+    right: Box<AstNode<N>>,
+    span: Span,
+  },
+  Group {
+    wrapping: Wrapping,
+    inner: Box<AstNode<N>>,
+    span: Span,
   },
-  None,
 }
 
-impl std::fmt::Debug for AstNode {
+impl<N: Numeric> std::fmt::Debug for AstNode<N> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
-      AstNode::Some { token, left, right } => {
-        write!(f, "{:?}({:?}, {:?})", token, left, right)
+      AstNode::Constant { value, .. } => write!(f, "const::{value}"),
+      AstNode::Variable { name, .. } => write!(f, "var::{name}"),
+      AstNode::Call { name, args, .. } => {
+        write!(f, "func::{name}(")?;
+        for (i, arg) in args.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{arg:?}")?;
+        }
+        write!(f, ")")
+      },
+      AstNode::Unary { op, operand, .. } => write!(f, "op::{op}({operand:?})"),
+      AstNode::Binary {
+        op, left, right, ..
+      } => write!(f, "op::{op}({left:?}, {right:?})"),
+      AstNode::Group {
+        wrapping, inner, ..
+      } => {
+        write!(
+          f,
+          "{}{inner:?}{}",
+          wrapping.open_char(),
+          wrapping.close_char()
+        )
       },
-      AstNode::None => write!(f, ""),
     }
   }
 }
 
-impl AstNode {
-  pub fn new(token: Token) -> Self {
-    Self::Some {
-      token,
-      left: Box::new(AstNode::None),
-      right: Box::new(AstNode::None),
-    }
+/// Writes `node`, wrapped in parentheses if `wrap` is set
+fn write_operand<N: Numeric>(
+  f: &mut std::fmt::Formatter<'_>,
+  node: &AstNode<N>,
+  wrap: bool,
+) -> std::fmt::Result {
+  match wrap {
+    true => write!(f, "({node})"),
+    false => write!(f, "{node}"),
   }
+}
 
-  pub fn children(&self) -> Option<(&Box<AstNode>, &Box<AstNode>)> {
+/// Infix notation with only the parentheses needed to parse back into the
+/// same tree
+impl<N: Numeric> std::fmt::Display for AstNode<N> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
-      Self::Some {
-        ref left,
-        ref right,
+      AstNode::Constant { value, .. } => value.fmt_operand(f),
+      AstNode::Variable { name, .. } => write!(f, "{name}"),
+      AstNode::Call { name, args, .. } => {
+        write!(f, "{name}(")?;
+        for (i, arg) in args.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{arg}")?;
+        }
+        write!(f, ")")
+      },
+      AstNode::Unary {
+        op,
+        fixity: Fixity::Prefix,
+        operand,
+        ..
+      } => {
+        write!(f, "{}", op.symbol)?;
+        let wrap = notation::wrap_prefix_operand(Layout::Inline, op, operand);
+        write_operand(f, operand, wrap)
+      },
+      AstNode::Unary {
+        op,
+        fixity: Fixity::Postfix,
+        operand,
+        ..
+      } => {
+        let wrap = notation::wrap_postfix_operand(Layout::Inline, operand);
+        write_operand(f, operand, wrap)?;
+        write!(f, "{}", op.symbol)
+      },
+      AstNode::Binary {
+        op, left, right, ..
+      } => {
+        let wrap_left = notation::wrap_left(Layout::Inline, op, left);
+        let wrap_right = notation::wrap_right(Layout::Inline, op, right);
+        write_operand(f, left, wrap_left)?;
+        write!(f, " {} ", op.symbol)?;
+        write_operand(f, right, wrap_right)
+      },
+      AstNode::Group {
+        wrapping: Wrapping::Bar,
+        inner,
         ..
-      } => Some((left, right)),
-      Self::None => None,
+      } => write!(f, "|{inner}|"),
+      AstNode::Group { inner, .. } => write!(f, "{inner}"),
     }
   }
+}
 
-  pub fn children_mut(
-    &mut self,
-  ) -> Option<(&mut Box<AstNode>, &mut Box<AstNode>)> {
+/// Compares structure only, ignoring spans and any grouping that doesn't
+/// change the meaning. Grouping a relative right operand, as in `200 + (10%)`,
+/// does
+impl<N: Numeric> PartialEq for AstNode<N> {
+  fn eq(&self, other: &Self) -> bool {
+    match (self.unwrapped(), other.unwrapped()) {
+      (
+        AstNode::Constant { value: a, .. },
+        AstNode::Constant { value: b, .. },
+      ) => a == b,
+      (
+        AstNode::Variable { name: a, .. },
+        AstNode::Variable { name: b, .. },
+      ) => a == b,
+      (
+        AstNode::Call {
+          name: a, args: x, ..
+        },
+        AstNode::Call {
+          name: b, args: y, ..
+        },
+      ) => a == b && x == y,
+      (
+        AstNode::Unary {
+          op: a,
+          fixity: x,
+          operand: l,
+          ..
+        },
+        AstNode::Unary {
+          op: b,
+          fixity: y,
+          operand: r,
+          ..
+        },
+      ) => a == b && x == y && l == r,
+      (
+        AstNode::Binary {
+          op: a,
+          left: l1,
+          right: r1,
+          ..
+        },
+        AstNode::Binary {
+          op: b,
+          left: l2,
+          right: r2,
+          ..
+        },
+      ) => {
+        a == b && l1 == l2 && r1 == r2 && r1.is_relative() == r2.is_relative()
+      },
+      (AstNode::Group { inner: a, .. }, AstNode::Group { inner: b, .. }) => {
+        a == b
+      },
+      _ => false,
+    }
+  }
+}
+
+impl<N: Numeric> AstNode<N> {
+  /// Span of the whole subtree, including any wrappings
+  pub fn span(&self) -> Span {
     match self {
-      Self::Some {
-        ref mut left,
-        ref mut right,
-        ..
-      } => Some((left, right)),
-      Self::None => None,
+      AstNode::Constant { span, .. }
+      | AstNode::Variable { span, .. }
+      | AstNode::Call { span, .. }
+      | AstNode::Unary { span, .. }
+      | AstNode::Binary { span, .. }
+      | AstNode::Group { span, .. } => *span,
     }
   }
 
-  pub const fn is_some(&self) -> bool {
+  /// Looks through parentheses and other wrappings that only group
+  pub(crate) fn unwrapped(&self) -> &AstNode<N> {
     match self {
-      Self::Some { .. } => true,
-      Self::None => false,
+      AstNode::Group {
+        wrapping: Wrapping::Paren | Wrapping::Curly | Wrapping::Square,
+        inner,
+        ..
+      } => inner.unwrapped(),
+      node => node,
     }
   }
 
-  pub const fn is_none(&self) -> bool {
-    !self.is_some()
+  /// Whether the node is a relative quantity like `10%`
+  pub(crate) fn is_relative(&self) -> bool {
+    matches!(
+      self,
+      AstNode::Unary {
+        op,
+        fixity: Fixity::Postfix,
+        ..
+      } if op.relative
+    )
   }
 
-  pub fn is_leaf(&self) -> bool {
-    match self {
-      Self::Some { left, right, .. } => left.is_none() && right.is_none(),
-      Self::None => false,
+  pub fn evaluate(&self, ctx: &Context<N>) -> MathResult<N> {
+    self.evaluate_with(ctx, &[], &[])
+  }
+
+  /// Like [AstNode::evaluate], with each of `names` bound to the value at
+  /// the same index in `values`, ahead of anything in `ctx`
+  pub fn evaluate_with(
+    &self,
+    ctx: &Context<N>,
+    names: &[String],
+    values: &[N],
+  ) -> MathResult<N> {
+    Evaluator { ctx, names, values }.evaluate(self)
+  }
+}
+
+/// Computes the value of a tree against a context
+struct Evaluator<'c, 'p, N: Numeric> {
+  ctx: &'c Context<'p, N>,
+  names: &'c [String],
+  values: &'c [N],
+}
+
+impl<N: Numeric> Evaluator<'_, '_, N> {
+  /// Errors get the location of the innermost node that doesn't have one
+  fn evaluate(&mut self, node: &AstNode<N>) -> MathResult<N> {
+    node.accept(self).map_err(|err| err.or_span(node.span()))
+  }
+}
+
+impl<N: Numeric> Visitor<N> for Evaluator<'_, '_, N> {
+  type Output = MathResult<N>;
+
+  fn visit_constant(&mut self, value: N, _span: Span) -> MathResult<N> {
+    Ok(value)
+  }
+
+  fn visit_variable(&mut self, name: &str, _span: Span) -> MathResult<N> {
+    let bound = self.names.iter().position(|n| n == name);
+    if let Some(value) = bound.and_then(|index| self.values.get(index)) {
+      return Ok(value.clone());
     }
+    self.ctx.read_variable(name.to_string())
   }
 
-  fn precedence(&self) -> (usize, usize) {
-    match self {
-      Self::Some {
-        token: Token::Operator { op, wrapping },
+  fn visit_call(
+    &mut self,
+    name: &str,
+    args: &[AstNode<N>],
+    _span: Span,
+  ) -> MathResult<N> {
+    let args = args
+      .iter()
+      .map(|arg| self.evaluate(arg))
+      .collect::<Result<Vec<_>, _>>()?;
+    self.ctx.compute_function(name.to_string(), &args)
+  }
+
+  fn visit_unary(
+    &mut self,
+    op: &Operation<N>,
+    fixity: Fixity,
+    operand: &AstNode<N>,
+    _span: Span,
+  ) -> MathResult<N> {
+    let operand = self.evaluate(operand)?;
+    match fixity {
+      Fixity::Prefix => op.perform_unary(operand),
+      Fixity::Postfix => op.perform_postfix(operand),
+    }
+  }
+
+  fn visit_binary(
+    &mut self,
+    op: &Operation<N>,
+    left: &AstNode<N>,
+    right: &AstNode<N>,
+    _span: Span,
+  ) -> MathResult<N> {
+    let (a, b) = (self.evaluate(left)?, self.evaluate(right)?);
+    match right.is_relative() {
+      true => op.perform_relative(a, b),
+      false => op.perform_binary(a, b),
+    }
+  }
+
+  fn visit_group(
+    &mut self,
+    wrapping: Wrapping,
+    inner: &AstNode<N>,
+    _span: Span,
+  ) -> MathResult<N> {
+    let inner = self.evaluate(inner)?;
+    match wrapping {
+      Wrapping::Bar => Ok(inner.abs()),
+      _ => Ok(inner),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ast<N: Numeric = Number> {
+  pub root: AstNode<N>,
+}
+
+impl<N: Numeric> std::fmt::Display for Ast<N> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.root)
+  }
+}
+
+impl<N: Numeric> Ast<N> {
+  pub fn new(tokens: Vec<Spanned<Token<N>>>) -> Result<Self, MathError> {
+    let mut parser = Parser::new(tokens);
+    if parser.peek().is_none() {
+      return Err(MathError::NoInput(Span::default()));
+    }
+    let root = parser.parse_expression(0)?;
+    // Everything has to be part of the one expression
+    if let Some(token) = parser.next() {
+      return Err(MathError::UnexpectedToken(
+        token.item.describe(),
+        token.span,
+      ));
+    }
+    Ok(Self { root })
+  }
+
+  pub fn evaluate(&self, ctx: &Context<N>) -> MathResult<N> {
+    self.root.evaluate(ctx)
+  }
+}
+
+/// A single line of input, which may update the context it runs in
+#[derive(Debug, Clone)]
+pub enum Statement<N: Numeric = Number> {
+  Expression(Ast<N>),
+  /// `x = 3`
+  Assignment {
+    name: String,
+    value: Ast<N>,
+    span: Span,
+  },
+  /// `f(x) = x^2 + 1`
+  FunctionDefinition {
+    name: String,
+    params: Vec<String>,
+    body: Ast<N>,
+    span: Span,
+  },
+}
+
+impl<N: Numeric> Statement<N> {
+  pub fn new(mut tokens: Vec<Spanned<Token<N>>>) -> Result<Self, MathError> {
+    let Some(index) =
+      tokens.iter().position(|t| matches!(t.item, Token::Assign))
+    else {
+      return Ok(Statement::Expression(Ast::new(tokens)?));
+    };
+    let body = tokens.split_off(index + 1);
+    let assign = tokens.pop().unwrap().span;
+    let body = match Ast::new(body) {
+      Err(MathError::NoInput(_)) => {
+        return Err(MathError::MissingOperand(Span::at(assign.end)))
+      },
+      body => body?,
+    };
+    let span = tokens
+      .iter()
+      .fold(Span::default(), |span, t| span.join(t.span));
+    match tokens.as_slice() {
+      [Spanned {
+        item: Token::Variable(name),
+        ..
+      }] => Ok(Statement::Assignment {
+        name: name.clone(),
+        value: body,
+        span,
+      }),
+      [Spanned {
+        item: Token::Function { name, .. },
         ..
-      } => (*wrapping, op.precedence),
-      Self::Some {
-        token: Token::Function { .. },
+      }, Spanned {
+        item: Token::Open(_),
         ..
-      } => (0, usize::MAX),
-      _ => (usize::MAX, usize::MAX),
+      }, params @ .., Spanned {
+        item: Token::Close(_),
+        ..
+      }] => Ok(Statement::FunctionDefinition {
+        name: name.clone(),
+        params: Self::parse_params(params)?,
+        body,
+        span,
+      }),
+      [] => Err(MathError::InvalidAssignment(assign)),
+      _ => Err(MathError::InvalidAssignment(span)),
     }
   }
 
-  pub fn compare(&self, other: &Self) -> Ordering {
-    let ordering = self.precedence().cmp(&other.precedence());
-    if ordering == Ordering::Equal {
-      if let Self::Some {
-        token:
-          Token::Operator {
-            op: Operation { associativity, .. },
-            ..
-          },
-        ..
-      } = self
-      {
-        return match associativity {
-          Associativity::Left => Ordering::Less,
-          Associativity::Right => Ordering::Greater,
-        };
+  /// Reads the comma separated names between a definition's wrappings
+  fn parse_params(
+    tokens: &[Spanned<Token<N>>],
+  ) -> Result<Vec<String>, MathError> {
+    let mut params = vec![];
+    for (index, token) in tokens.iter().enumerate() {
+      match (&token.item, index % 2) {
+        (Token::Variable(name), 0) => params.push(name.clone()),
+        (Token::Separator, 1) => {},
+        _ => return Err(MathError::InvalidAssignment(token.span)),
+      }
+    }
+    // A trailing separator leaves a parameter missing
+    if let Some(last) = tokens.last() {
+      if matches!(last.item, Token::Separator) {
+        return Err(MathError::InvalidAssignment(last.span));
       }
     }
-    ordering
+    Ok(params)
   }
 
-  pub fn evaluate(&self, ctx: &Context) -> MathResult {
-    let (token, left, right) = match self {
-      AstNode::Some { token, left, right } => (token, left, right),
-      AstNode::None => return Err(MathError::Undefined),
-    };
-    match token {
-      Token::Constant(c) => Ok(*c),
-      Token::Variable(v) => ctx.read_variable(v.to_string()),
-      Token::Function(f) => {
-        // Check if parsing messed up somewhere
-        if left.is_some() {
-          return Err(MathError::Undefined);
-        }
-        ctx.compute_function(f.to_string(), right.evaluate(ctx))
+  /// Runs the statement, returning the value of expressions and assignments
+  pub fn execute(&self, ctx: &mut Context<N>) -> Result<Option<N>, MathError> {
+    match self {
+      Statement::Expression(ast) => ast.evaluate(ctx).map(Some),
+      Statement::Assignment { name, value, .. } => {
+        let value = value.evaluate(ctx)?;
+        ctx.assign_variable(name.to_string(), value.clone());
+        Ok(Some(value))
       },
-      Token::Operator { op, .. } => {
-        match (left.evaluate(ctx), right.evaluate(ctx)) {
-          (Ok(a), Ok(b)) => op.perform_binary(a, b),
-          (Err(_), Ok(a)) => op.perform_unary(a),
-          (Ok(a), Err(_)) => op.perform_unary(a),
-          _ => Err(MathError::Undefined),
-        }
+      Statement::FunctionDefinition {
+        name, params, body, ..
+      } => {
+        ctx.assign_function(
+          name.to_string(),
+          Function::AstDefined(body.clone(), params.clone()),
+        );
+        Ok(None)
       },
     }
   }
 }
 
+impl<N: Numeric> std::fmt::Display for Statement<N> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Statement::Expression(ast) => write!(f, "{ast}"),
+      Statement::Assignment { name, value, .. } => {
+        write!(f, "{name} = {value}")
+      },
+      Statement::FunctionDefinition {
+        name, params, body, ..
+      } => write!(f, "{name}({}) = {body}", params.join(", ")),
+    }
+  }
+}
+
+/// Statements separated by `;` or newlines, run in order against one context.
+/// Newlines only separate statements lexed with [crate::lexer::lex_program]
 #[derive(Debug, Clone)]
-pub struct Ast {
-  pub root: Box<AstNode>,
+pub struct Program<N: Numeric = Number> {
+  pub statements: Vec<Statement<N>>,
 }
 
-impl Ast {
-  pub fn new(tokens: Vec<Token>) -> Self {
-    let mut this = Self {
-      root: Box::new(AstNode::None),
-    };
-    for t in tokens {
-      this.push(AstNode::new(t));
+impl<N: Numeric> Program<N> {
+  /// Blank statements, as from consecutive or trailing separators, are
+  /// skipped
+  pub fn new(tokens: Vec<Spanned<Token<N>>>) -> Result<Self, MathError> {
+    let mut statements = vec![];
+    let mut current = vec![];
+    for token in tokens {
+      match token.item {
+        Token::Terminator if current.is_empty() => {},
+        Token::Terminator => {
+          statements.push(Statement::new(std::mem::take(&mut current))?)
+        },
+        _ => current.push(token),
+      }
+    }
+    if !current.is_empty() {
+      statements.push(Statement::new(current)?);
+    }
+    if statements.is_empty() {
+      return Err(MathError::NoInput(Span::default()));
     }
-    this
+    Ok(Program { statements })
   }
 
-  fn swim(old: &mut Box<AstNode>, mut new: Box<AstNode>) {
-    let (left, right) = new.children_mut().unwrap();
-    *right = Box::new(AstNode::None);
-    *left = old.clone();
-    *old = new;
+  /// Result of every statement in order, so the last one is the final
+  /// value. Stops at the first error
+  pub fn execute(
+    &self,
+    ctx: &mut Context<N>,
+  ) -> Result<Vec<Option<N>>, MathError> {
+    self.statements.iter().map(|s| s.execute(ctx)).collect()
+  }
+}
+
+/// Precedence climbing parser over a token stream
+struct Parser<N: Numeric> {
+  tokens: Vec<Spanned<Token<N>>>,
+  position: usize,
+  /// Where missing operands at the end of input are reported
+  end: Span,
+}
+
+impl<N: Numeric> Parser<N> {
+  fn new(tokens: Vec<Spanned<Token<N>>>) -> Self {
+    let end = tokens
+      .last()
+      .map_or(Span::default(), |t| Span::at(t.span.end));
+    Self {
+      tokens,
+      position: 0,
+      end,
+    }
+  }
+
+  fn peek(&self) -> Option<&Spanned<Token<N>>> {
+    self.tokens.get(self.position)
+  }
+
+  fn peek_item(&self) -> Option<&Token<N>> {
+    self.peek().map(|t| &t.item)
   }
 
-  fn sink(mut ptr: &mut Box<AstNode>) -> &mut Box<AstNode> {
-    let (_, right) = ptr.children_mut().unwrap();
-    ptr = right;
-    ptr
+  fn next(&mut self) -> Option<Spanned<Token<N>>> {
+    let token = self.tokens.get(self.position).cloned();
+    self.position += 1;
+    token
   }
 
-  pub fn evaluate(&self, ctx: &Context) -> MathResult {
-    if let AstNode::None = *self.root {
-      return Err(MathError::NoInput);
+  /// Whether the token after the next one can begin an operand, which tells
+  /// apart binary and postfix uses of the same symbol
+  fn operand_follows(&self) -> bool {
+    match self.tokens.get(self.position + 1).map(|t| &t.item) {
+      Some(Token::Operator(op)) => op.unary_func.is_some(),
+      Some(Token::Close(_) | Token::Separator) | None => false,
+      Some(_) => true,
     }
-    self.root.evaluate(ctx)
   }
 
-  pub fn push(&mut self, node: AstNode) {
-    if node.is_none() {
-      return;
+  /// Parses operators binding at least as tightly as `min_precedence`
+  fn parse_expression(
+    &mut self,
+    min_precedence: usize,
+  ) -> Result<AstNode<N>, MathError> {
+    let mut left = self.parse_operand()?;
+    while let Some(Token::Operator(op)) = self.peek_item() {
+      let op = op.clone();
+      if op.precedence < min_precedence {
+        break;
+      }
+      let binary = op.binary_func.is_some()
+        && (op.postfix_func.is_none() || self.operand_follows());
+      let token = self.next().unwrap();
+      if binary {
+        let next_precedence = match op.associativity {
+          Associativity::Left => op.precedence + 1,
+          Associativity::Right => op.precedence,
+        };
+        let right = self.parse_expression(next_precedence)?;
+        let span = left.span().join(right.span());
+        left = AstNode::Binary {
+          op,
+          left: Box::new(left),
+          right: Box::new(right),
+          span,
+        };
+      } else if op.postfix_func.is_some() {
+        let span = left.span().join(token.span);
+        left = AstNode::Unary {
+          op,
+          fixity: Fixity::Postfix,
+          operand: Box::new(left),
+          span,
+        };
+      } else {
+        return Err(MathError::UnexpectedToken(
+          token.item.describe(),
+          token.span,
+        ));
+      }
     }
-    let node = Box::new(node);
-    let mut ptr = &mut self.root;
-    loop {
-      // Replace
-      if ptr.is_none() {
-        *ptr = node;
-        return;
+    Ok(left)
+  }
+
+  /// Parses a single operand, along with any prefix operators
+  fn parse_operand(&mut self) -> Result<AstNode<N>, MathError> {
+    let Some(Spanned { item, span }) = self.next() else {
+      return Err(MathError::MissingOperand(self.end));
+    };
+    match item {
+      Token::Constant(value) => Ok(AstNode::Constant { value, span }),
+      Token::Variable(name) => Ok(AstNode::Variable { name, span }),
+      Token::Function { name, arity } => self.parse_call(name, arity, span),
+      Token::Open(wrapping) => {
+        let inner = self.parse_expression(0)?;
+        let close = self.expect_close(wrapping, span)?;
+        Ok(AstNode::Group {
+          wrapping,
+          inner: Box::new(inner),
+          span: span.join(close),
+        })
+      },
+      Token::Operator(op) if op.unary_func.is_some() => {
+        let operand = self.parse_expression(op.unary_precedence)?;
+        let span = span.join(operand.span());
+        Ok(AstNode::Unary {
+          op,
+          fixity: Fixity::Prefix,
+          operand: Box::new(operand),
+          span,
+        })
+      },
+      // Closing wraps, separators and operators that need a left operand
+      _ => Err(MathError::MissingOperand(span)),
+    }
+  }
+
+  fn parse_call(
+    &mut self,
+    name: String,
+    arity: usize,
+    name_span: Span,
+  ) -> Result<AstNode<N>, MathError> {
+    let (wrapping, open) = match self.peek() {
+      Some(Spanned {
+        item: Token::Open(wrapping),
+        span,
+      }) => (*wrapping, *span),
+      // Bare application like `sin x`
+      _ => {
+        let arg = self.parse_bare_argument()?;
+        let span = name_span.join(arg.span());
+        return Ok(AstNode::Call {
+          name,
+          args: vec![arg],
+          span,
+        });
+      },
+    };
+    self.next();
+    let mut args = Vec::with_capacity(arity);
+    if !matches!(self.peek_item(), Some(Token::Close(_))) {
+      loop {
+        args.push(self.parse_expression(0)?);
+        if !matches!(self.peek_item(), Some(Token::Separator)) {
+          break;
+        }
+        self.next();
       }
+    }
+    let close = self.expect_close(wrapping, open)?;
+    Ok(AstNode::Call {
+      name,
+      args,
+      span: name_span.join(close),
+    })
+  }
 
-      match node.compare(ptr) {
-        // Swim
-        Ordering::Less => {
-          Self::swim(ptr, node);
-          return;
-        },
-        // Sink
-        Ordering::Equal | Ordering::Greater => {
-          ptr = Self::sink(ptr);
-        },
+  /// Argument of a call without wrappings. Implicit multiplication carries
+  /// it on, so `sin 2x` is `sin(2x)`, unless another call follows, so that
+  /// `sin x cos x` is still a product of two calls
+  fn parse_bare_argument(&mut self) -> Result<AstNode<N>, MathError> {
+    let mut arg = self.parse_expression(BARE_CALL_PRECEDENCE)?;
+    // Implicit operators are the only ones taking up no space
+    while let Some(Spanned {
+      item: Token::Operator(op),
+      span,
+    }) = self.peek()
+    {
+      let call_follows = matches!(
+        self.tokens.get(self.position + 1).map(|t| &t.item),
+        Some(Token::Function { .. })
+      );
+      if !span.is_empty() || call_follows {
+        break;
       }
+      let op = op.clone();
+      self.next();
+      let right = self.parse_expression(BARE_CALL_PRECEDENCE)?;
+      let span = arg.span().join(right.span());
+      arg = AstNode::Binary {
+        op,
+        left: Box::new(arg),
+        right: Box::new(right),
+        span,
+      };
+    }
+    Ok(arg)
+  }
+
+  /// Consumes the wrapping closing the one opened at `open`
+  fn expect_close(
+    &mut self,
+    wrapping: Wrapping,
+    open: Span,
+  ) -> Result<Span, MathError> {
+    match self.next() {
+      Some(Spanned {
+        item: Token::Close(w),
+        span,
+      }) if w == wrapping => Ok(span),
+      Some(Spanned {
+        item: Token::Close(_),
+        span,
+      }) => Err(MathError::UnmatchedWrapping(span)),
+      Some(Spanned {
+        item: Token::Separator,
+        span,
+      }) => Err(MathError::MisplacedSeparator(span)),
+      Some(token) => Err(MathError::UnexpectedToken(
+        token.item.describe(),
+        token.span,
+      )),
+      None => Err(MathError::UnmatchedWrapping(open)),
     }
   }
 }
@@ -0,0 +1,338 @@
+//! Exact rational arithmetic, so `1/3 + 1/6` is exactly `1/2`. Addition,
+//! subtraction, multiplication, division and powers with exact results stay
+//! exact. Everything else, like `sin` or `pi`, falls back to floating point
+//! and the result says so
+
+use std::{
+  cmp::Ordering,
+  ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use num::{
+  BigInt, BigRational, FromPrimitive, Num, One, Signed, ToPrimitive, Zero,
+};
+
+use crate::{
+  error::{MathError, MathResult},
+  number::{sanitize_real, Base, Number, Numeric},
+  span::Span,
+};
+
+/// Results past this many bits are too big to compute, so `2^10^10` fails
+/// instead of running out of memory
+pub(crate) const MAX_BITS: u64 = 1 << 20;
+
+/// Literals can't have exponents past this, for the same reason
+const MAX_EXPONENT: i64 = 100_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rational {
+  Exact(BigRational),
+  /// Result of something with no exact rational value
+  Float(f64),
+}
+
+impl Rational {
+  pub fn is_exact(&self) -> bool {
+    matches!(self, Rational::Exact(_))
+  }
+
+  /// Nearest floating point value
+  pub fn to_f64(&self) -> f64 {
+    match self {
+      Rational::Exact(r) => r.to_f64().unwrap_or(f64::NAN),
+      Rational::Float(f) => *f,
+    }
+  }
+
+  /// Exact when both sides are, floating point otherwise
+  fn combine(
+    self,
+    other: Self,
+    exact: impl FnOnce(BigRational, BigRational) -> BigRational,
+    float: impl FnOnce(f64, f64) -> f64,
+  ) -> Self {
+    match (self, other) {
+      (Rational::Exact(a), Rational::Exact(b)) => Rational::Exact(exact(a, b)),
+      (a, b) => Rational::Float(float(a.to_f64(), b.to_f64())),
+    }
+  }
+}
+
+/// Parses an integer, decimal or scientific literal without rounding, so
+/// `0.1` is exactly one tenth
+pub(crate) fn parse_exact(literal: &str) -> Option<BigRational> {
+  let literal: &str = &literal.replace('_', "");
+  let mut chars = literal.chars();
+  if let (Some('0'), Some(marker)) = (chars.next(), chars.next()) {
+    if let Some(base) = Base::from_marker(marker) {
+      let int = BigInt::from_str_radix(&literal[2..], base.radix()).ok()?;
+      return Some(BigRational::from_integer(int));
+    }
+  }
+  let (mantissa, exponent) = match literal.split_once(['e', 'E']) {
+    Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+    None => (literal, 0),
+  };
+  let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+  let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+  if whole.is_empty() && fraction.is_empty()
+    || !all_digits(whole)
+    || !all_digits(fraction)
+    || exponent.abs() > MAX_EXPONENT
+  {
+    return None;
+  }
+  let digits =
+    BigInt::from_str_radix(&format!("{whole}{fraction}"), 10).ok()?;
+  let scale = exponent - fraction.len() as i64;
+  let power = BigInt::from(10).pow(scale.unsigned_abs() as u32);
+  Some(match scale >= 0 {
+    true => BigRational::from_integer(digits * power),
+    false => BigRational::new(digits, power),
+  })
+}
+
+fn too_big<T>() -> MathResult<T> {
+  Err(MathError::TooBig(Span::default()))
+}
+
+/// `base^exponent` for integer exponents
+fn exact_power(
+  base: &BigRational,
+  exponent: &BigInt,
+) -> MathResult<BigRational> {
+  if base.is_zero() {
+    return match exponent.sign() {
+      num::bigint::Sign::Minus => {
+        Err(MathError::DivisionByZero(Span::default()))
+      },
+      num::bigint::Sign::NoSign => Ok(BigRational::one()),
+      num::bigint::Sign::Plus => Ok(BigRational::zero()),
+    };
+  }
+  // Powers of one and minus one never grow
+  if base.abs().is_one() {
+    return match exponent % 2 == BigInt::zero() {
+      true => Ok(BigRational::one()),
+      false => Ok(base.clone()),
+    };
+  }
+  let bits = base.numer().bits().max(base.denom().bits());
+  let Some(magnitude) = exponent.abs().to_u64() else {
+    return too_big();
+  };
+  if bits.saturating_mul(magnitude) > MAX_BITS {
+    return too_big();
+  }
+  let power = BigRational::new(
+    base.numer().pow(magnitude as u32),
+    base.denom().pow(magnitude as u32),
+  );
+  match exponent.is_negative() {
+    true => Ok(power.recip()),
+    false => Ok(power),
+  }
+}
+
+/// The `n`th root of a non-negative number, if it's rational
+fn exact_root(num: &BigRational, n: u32) -> Option<BigRational> {
+  if num.is_negative() {
+    return None;
+  }
+  let (numer, denom) = (num.numer().nth_root(n), num.denom().nth_root(n));
+  let root = BigRational::new(numer, denom);
+  match root.pow(n as i32) == *num {
+    true => Some(root),
+    false => None,
+  }
+}
+
+impl Numeric for Rational {
+  /// Imaginary literals like `2i` aren't numbers here
+  fn parse_literal(literal: &str) -> Option<Self> {
+    parse_exact(literal).map(Rational::Exact)
+  }
+
+  fn to_number(&self) -> Number {
+    Number::new(self.to_f64(), 0.0)
+  }
+
+  fn from_number(num: Number) -> MathResult<Self> {
+    match num.im {
+      0.0 => Ok(Rational::Float(num.re)),
+      _ => Err(MathError::ComplexResult(Span::default())),
+    }
+  }
+
+  fn sanitize(self) -> MathResult<Self> {
+    match self {
+      Rational::Exact(r) => Ok(Rational::Exact(r)),
+      Rational::Float(f) => sanitize_real(f).map(Rational::Float),
+    }
+  }
+
+  fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Rational::Exact(r) if !r.is_integer() => write!(f, "({r})"),
+      num => write!(f, "{num}"),
+    }
+  }
+
+  fn real_cmp(&self, other: &Self) -> Option<Ordering> {
+    match (self, other) {
+      (Rational::Exact(a), Rational::Exact(b)) => Some(a.cmp(b)),
+      (a, b) => a.to_f64().partial_cmp(&b.to_f64()),
+    }
+  }
+
+  fn abs(self) -> Self {
+    match self {
+      Rational::Exact(r) => Rational::Exact(r.abs()),
+      Rational::Float(f) => Rational::Float(f.abs()),
+    }
+  }
+
+  fn pow(self, exponent: Self) -> MathResult<Self> {
+    if let (Rational::Exact(base), Rational::Exact(exponent)) =
+      (&self, &exponent)
+    {
+      if exponent.is_integer() {
+        return exact_power(base, &exponent.to_integer()).map(Rational::Exact);
+      }
+      // Perfect powers like `8^(2/3)` still have exact roots
+      if let Some(root) = exponent
+        .denom()
+        .to_u32()
+        .filter(|n| *n < 1 << 16)
+        .and_then(|n| exact_root(base, n))
+      {
+        return exact_power(&root, exponent.numer()).map(Rational::Exact);
+      }
+    }
+    let (base, exponent) = (self.to_f64(), exponent.to_f64());
+    match base >= 0.0 || exponent.fract() == 0.0 {
+      true => sanitize_real(base.powf(exponent)).map(Rational::Float),
+      false => Err(MathError::ComplexResult(Span::default())),
+    }
+  }
+
+  fn sqrt(self) -> MathResult<Self> {
+    match &self {
+      Rational::Exact(r) if !r.is_negative() => match exact_root(r, 2) {
+        Some(root) => Ok(Rational::Exact(root)),
+        None => sanitize_real(self.to_f64().sqrt()).map(Rational::Float),
+      },
+      num if num.to_f64() >= 0.0 => {
+        sanitize_real(num.to_f64().sqrt()).map(Rational::Float)
+      },
+      _ => Err(MathError::ComplexResult(Span::default())),
+    }
+  }
+
+  fn factorial(self) -> MathResult<Self> {
+    match &self {
+      Rational::Exact(r) if r.is_integer() && !r.is_negative() => {
+        let n = r.to_integer().to_u64().filter(|n| *n < 1 << 16);
+        let Some(n) = n else {
+          return too_big();
+        };
+        let product = (1..=n).fold(BigInt::one(), |acc, k| acc * k);
+        Ok(Rational::Exact(BigRational::from_integer(product)))
+      },
+      _ => Rational::from_number(crate::number::factorial(self.to_number())?),
+    }
+  }
+}
+
+impl std::fmt::Display for Rational {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Rational::Exact(r) => write!(f, "{r}"),
+      Rational::Float(x) => write!(f, "{x}"),
+    }
+  }
+}
+
+impl Add for Rational {
+  type Output = Self;
+
+  fn add(self, other: Self) -> Self {
+    self.combine(other, |a, b| a + b, |a, b| a + b)
+  }
+}
+
+impl Sub for Rational {
+  type Output = Self;
+
+  fn sub(self, other: Self) -> Self {
+    self.combine(other, |a, b| a - b, |a, b| a - b)
+  }
+}
+
+impl Mul for Rational {
+  type Output = Self;
+
+  fn mul(self, other: Self) -> Self {
+    self.combine(other, |a, b| a * b, |a, b| a * b)
+  }
+}
+
+/// Exact division by zero has no value, so it goes through floating point
+/// and comes out infinite or NaN instead of panicking
+impl Div for Rational {
+  type Output = Self;
+
+  fn div(self, other: Self) -> Self {
+    match (self, other) {
+      (Rational::Exact(a), Rational::Exact(b)) if !b.is_zero() => {
+        Rational::Exact(a / b)
+      },
+      (a, b) => Rational::Float(a.to_f64() / b.to_f64()),
+    }
+  }
+}
+
+impl Neg for Rational {
+  type Output = Self;
+
+  fn neg(self) -> Self {
+    match self {
+      Rational::Exact(r) => Rational::Exact(-r),
+      Rational::Float(f) => Rational::Float(-f),
+    }
+  }
+}
+
+impl Zero for Rational {
+  fn zero() -> Self {
+    Rational::Exact(BigRational::zero())
+  }
+
+  fn is_zero(&self) -> bool {
+    match self {
+      Rational::Exact(r) => r.is_zero(),
+      Rational::Float(f) => *f == 0.0,
+    }
+  }
+}
+
+impl One for Rational {
+  fn one() -> Self {
+    Rational::Exact(BigRational::one())
+  }
+}
+
+impl FromPrimitive for Rational {
+  fn from_i64(n: i64) -> Option<Self> {
+    Some(Rational::Exact(BigRational::from_integer(n.into())))
+  }
+
+  fn from_u64(n: u64) -> Option<Self> {
+    Some(Rational::Exact(BigRational::from_integer(n.into())))
+  }
+
+  fn from_f64(n: f64) -> Option<Self> {
+    Some(Rational::Float(n))
+  }
+}
@@ -0,0 +1,100 @@
+//! Real number backend, for callers that never want complex results. Anything
+//! that would leave the real line is a [MathError::ComplexResult] instead
+
+use crate::{
+  error::{MathError, MathResult},
+  number::{parse_literal, sanitize_real, Number, Numeric},
+  span::Span,
+};
+
+fn complex<T>() -> MathResult<T> {
+  Err(MathError::ComplexResult(Span::default()))
+}
+
+impl Numeric for f64 {
+  /// Imaginary literals like `2i` aren't numbers here
+  fn parse_literal(literal: &str) -> Option<Self> {
+    match parse_literal(literal)? {
+      num if num.im == 0.0 => Some(num.re),
+      _ => None,
+    }
+  }
+
+  fn to_number(&self) -> Number {
+    Number::new(*self, 0.0)
+  }
+
+  fn from_number(num: Number) -> MathResult<Self> {
+    match num.im {
+      0.0 => Ok(num.re),
+      _ => complex(),
+    }
+  }
+
+  fn sanitize(self) -> MathResult<Self> {
+    sanitize_real(self)
+  }
+
+  fn abs(self) -> Self {
+    f64::abs(self)
+  }
+
+  fn pow(self, exponent: Self) -> MathResult<Self> {
+    match self >= 0.0 || exponent.fract() == 0.0 {
+      true => self.powf(exponent).sanitize(),
+      false => complex(),
+    }
+  }
+
+  fn sqrt(self) -> MathResult<Self> {
+    match self >= 0.0 {
+      true => f64::sqrt(self).sanitize(),
+      false => complex(),
+    }
+  }
+
+  fn exp(self) -> MathResult<Self> {
+    f64::exp(self).sanitize()
+  }
+
+  fn ln(self) -> MathResult<Self> {
+    match self >= 0.0 {
+      true => f64::ln(self).sanitize(),
+      false => complex(),
+    }
+  }
+
+  fn sin(self) -> MathResult<Self> {
+    f64::sin(self).sanitize()
+  }
+
+  fn cos(self) -> MathResult<Self> {
+    f64::cos(self).sanitize()
+  }
+
+  fn tan(self) -> MathResult<Self> {
+    f64::tan(self).sanitize()
+  }
+
+  fn asin(self) -> MathResult<Self> {
+    match (-1.0..=1.0).contains(&self) {
+      true => f64::asin(self).sanitize(),
+      false => complex(),
+    }
+  }
+
+  fn acos(self) -> MathResult<Self> {
+    match (-1.0..=1.0).contains(&self) {
+      true => f64::acos(self).sanitize(),
+      false => complex(),
+    }
+  }
+
+  fn atan(self) -> MathResult<Self> {
+    f64::atan(self).sanitize()
+  }
+
+  fn atan2(self, x: Self) -> MathResult<Self> {
+    f64::atan2(self, x).sanitize()
+  }
+}
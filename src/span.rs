@@ -0,0 +1,76 @@
+use std::ops::Range;
+
+/// Byte range of a piece of the input string
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+  pub start: usize,
+  pub end: usize,
+}
+
+impl Span {
+  pub const fn new(start: usize, end: usize) -> Self {
+    Self { start, end }
+  }
+
+  /// Zero width span at `pos`
+  pub const fn at(pos: usize) -> Self {
+    Self::new(pos, pos)
+  }
+
+  pub const fn len(&self) -> usize {
+    self.end - self.start
+  }
+
+  /// Empty spans carry no location, and are replaced when an error bubbles
+  /// up through a node that knows where it is
+  pub const fn is_empty(&self) -> bool {
+    self.start == self.end
+  }
+
+  /// Smallest span covering both `self` and `other`
+  pub fn join(self, other: Span) -> Span {
+    if self.is_empty() {
+      return other;
+    }
+    if other.is_empty() {
+      return self;
+    }
+    Span::new(self.start.min(other.start), self.end.max(other.end))
+  }
+
+  /// Converts the byte range into a char range of `input`
+  pub fn chars(&self, input: &str) -> Range<usize> {
+    let start = input[..self.start.min(input.len())].chars().count();
+    let len = input
+      .get(self.start..self.end.min(input.len()))
+      .map_or(0, |s| s.chars().count());
+    start..start + len
+  }
+
+  /// Prints the line of `input` containing the span, with a row of carets
+  /// underneath the spanned characters
+  pub fn underline(&self, input: &str) -> String {
+    let start = self.start.min(input.len());
+    let line_start = input[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[start..].find('\n').map_or(input.len(), |i| start + i);
+    let line = &input[line_start..line_end];
+    let local =
+      Span::new(start - line_start, self.end.min(line_end) - line_start);
+    let cols = local.chars(line);
+    let width = cols.len().max(1);
+    format!("{}\n{}{}", line, " ".repeat(cols.start), "^".repeat(width))
+  }
+}
+
+/// Attaches a location in the input to a lexeme or token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+  pub item: T,
+  pub span: Span,
+}
+
+impl<T> Spanned<T> {
+  pub const fn new(item: T, span: Span) -> Self {
+    Self { item, span }
+  }
+}
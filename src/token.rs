@@ -1,124 +1,292 @@
 use crate::{
   error::MathError,
+  functions::Function,
   lexer::{Lexeme, Wrapping},
-  number::Number,
+  number::{Number, Numeric},
   operator::{Operation, OperatorSet},
+  span::{Span, Spanned},
 };
 
-pub type WrappingLevel = usize;
-
 #[derive(Clone)]
-pub enum Token {
-  Constant(Number),
+pub enum Token<N = Number> {
+  Constant(N),
   Variable(String),
-  Function(String),
-  Operator {
-    op: Operation,
-    wrapping: WrappingLevel,
-  },
+  Function { name: String, arity: usize },
+  Operator(Operation<N>),
+  Open(Wrapping),
+  Close(Wrapping),
+  Separator,
+  Assign,
+  Terminator,
 }
 
-impl Token {
+impl<N: Numeric> Token<N> {
   pub fn is_numeric(&self) -> bool {
+    matches!(self, Token::Constant(_) | Token::Variable(_))
+  }
+
+  /// Whether an operand can end with this token, so that anything numeric
+  /// following it is implicitly operated on
+  pub fn ends_operand(&self) -> bool {
     match self {
-      Token::Constant(_) | Token::Variable(_) => true,
-      _ => false,
+      Token::Operator(op) => op.is_postfix_only(),
+      Token::Close(_) => true,
+      _ => self.is_numeric(),
+    }
+  }
+
+  /// Short human readable name, for error messages
+  pub fn describe(&self) -> String {
+    match self {
+      Token::Constant(n) => format!("number {n}"),
+      Token::Variable(s) => format!("variable {s}"),
+      Token::Function { name, .. } => format!("function {name}()"),
+      Token::Operator(op) => format!("{op} operator"),
+      Token::Open(w) => format!("'{}'", w.open_char()),
+      Token::Close(w) => format!("'{}'", w.close_char()),
+      Token::Separator => "','".to_string(),
+      Token::Assign => "'='".to_string(),
+      Token::Terminator => "end of statement".to_string(),
     }
   }
 }
 
-impl std::fmt::Debug for Token {
+impl<N: Numeric> std::fmt::Debug for Token<N> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
       Token::Constant(n) => write!(f, "const::{n}"),
       Token::Variable(s) => write!(f, "var::{s}"),
-      Token::Function(s) => write!(f, "func::{s}"),
-      Token::Operator { op, .. } => write!(f, "op::{op}"),
+      Token::Function { name, arity } => write!(f, "func::{name}/{arity}"),
+      Token::Operator(op) => write!(f, "op::{op}"),
+      Token::Open(w) => write!(f, "open::{}", w.open_char()),
+      Token::Close(w) => write!(f, "close::{}", w.close_char()),
+      Token::Separator => write!(f, "sep::,"),
+      Token::Assign => write!(f, "assign::="),
+      Token::Terminator => write!(f, "term::;"),
     }
   }
 }
 
-pub fn tokenize(
-  op_set: OperatorSet,
-  lexemes: Vec<Lexeme>,
-) -> Result<Vec<Token>, MathError> {
-  let mut wrappings: Vec<Wrapping> = vec![];
+/// Counts the arguments between the wrapping at `open` and its partner
+fn count_arguments(lexemes: &[Spanned<Lexeme>], open: usize) -> usize {
+  let mut depth = 0;
+  let mut separators = 0;
+  for (offset, lexeme) in lexemes[open..].iter().enumerate() {
+    match lexeme.item {
+      Lexeme::LeftWrap(_) => depth += 1,
+      Lexeme::RightWrap(_) => {
+        depth -= 1;
+        if depth == 0 {
+          // Nothing between the wrappings means no arguments at all
+          if offset == 1 {
+            return 0;
+          }
+          break;
+        }
+      },
+      Lexeme::Separator if depth == 1 => separators += 1,
+      _ => {},
+    }
+  }
+  separators + 1
+}
+
+/// Whether the lexemes after the name at `index` start an argument for it,
+/// as in `sin x`. Otherwise the name is a variable, as in `gamma = 0.5772` or
+/// `2 gamma`. A sign only starts an argument when it's spaced away from the
+/// name but not from what it signs, so `sin -x` is a call and `gamma - 1` isn't
+fn starts_argument<N: Numeric>(
+  op_set: &OperatorSet<N>,
+  lexemes: &[Spanned<Lexeme>],
+  index: usize,
+  wrappings: &[(Spanned<Wrapping>, bool)],
+) -> bool {
+  let Some(next) = lexemes.get(index + 1) else {
+    return false;
+  };
+  match &next.item {
+    Lexeme::Number(_) | Lexeme::Identifier(_) | Lexeme::Variable(_) => true,
+    // Inside bars, a bar closes rather than opens an argument
+    Lexeme::Bar => wrappings
+      .last()
+      .is_none_or(|(open, _)| open.item != Wrapping::Bar),
+    Lexeme::Special(c) => match op_set.get(c) {
+      Some(op) if op.is_prefix_only() => true,
+      Some(op) if op.unary_func.is_some() => {
+        next.span.start > lexemes[index].span.end
+          && lexemes
+            .get(index + 2)
+            .is_some_and(|after| after.span.start == next.span.end)
+      },
+      _ => false,
+    },
+    _ => false,
+  }
+}
+
+pub fn tokenize<N: Numeric>(
+  op_set: &OperatorSet<N>,
+  lexemes: Vec<Spanned<Lexeme>>,
+) -> Result<Vec<Spanned<Token<N>>>, MathError> {
+  // Each open wrapping remembers whether it holds function arguments
+  let mut wrappings: Vec<(Spanned<Wrapping>, bool)> = vec![];
+  let mut call_pending = false;
   let mut tokens = vec![];
+  // Implicit operators take up no space, so they sit right before the lexeme
+  // that caused them
+  let implicit = |span: Span| {
+    Spanned::new(
+      Token::Operator(op_set.implicit().clone()),
+      Span::at(span.start),
+    )
+  };
   for index in 0..lexemes.len() {
-    match &lexemes[index] {
+    let Spanned { item, span } = &lexemes[index];
+    let span = *span;
+    match item {
       // Parse number
       Lexeme::Number(s) => {
-        let n = match s.parse::<Number>() {
-          Ok(n) => n,
-          Err(_) => return Err(MathError::BadNumber),
+        let n = match N::parse_literal(s) {
+          Some(n) => n,
+          None => return Err(MathError::BadNumber(span)),
         };
-        if tokens.last().is_some_and(|t: &Token| t.is_numeric()) {
-          tokens.push(Token::Operator {
-            op: op_set.implicit().clone(),
-            wrapping: wrappings.len(),
-          });
+        if tokens
+          .last()
+          .is_some_and(|t: &Spanned<Token<N>>| t.item.ends_operand())
+        {
+          tokens.push(implicit(span));
         }
-        tokens.push(Token::Constant(n));
+        tokens.push(Spanned::new(Token::Constant(n), span));
       },
       // Discriminate between functions and vars
-      Lexeme::Identifier(s) => match lexemes.get(index + 1) {
+      Lexeme::Identifier(s) => match lexemes.get(index + 1).map(|l| &l.item) {
         Some(Lexeme::LeftWrap(_)) => {
           // Insert implicit op
-          if tokens.last().is_some_and(|t: &Token| t.is_numeric()) {
-            tokens.push(Token::Operator {
-              op: op_set.implicit().clone(),
-              wrapping: wrappings.len(),
-            });
+          if tokens
+            .last()
+            .is_some_and(|t: &Spanned<Token<N>>| t.item.ends_operand())
+          {
+            tokens.push(implicit(span));
           }
-          tokens.push(Token::Function(s.clone()));
+          tokens.push(Spanned::new(
+            Token::Function {
+              name: s.clone(),
+              arity: count_arguments(&lexemes, index + 1),
+            },
+            span,
+          ));
+          call_pending = true;
+        },
+        // Built in functions can be applied without wrapping, as in `sin x`.
+        // How much of what follows is the argument is up to the parser
+        _ if Function::is_builtin(s)
+          && starts_argument(op_set, &lexemes, index, &wrappings) =>
+        {
+          if tokens
+            .last()
+            .is_some_and(|t: &Spanned<Token<N>>| t.item.ends_operand())
+          {
+            tokens.push(implicit(span));
+          }
+          tokens.push(Spanned::new(
+            Token::Function {
+              name: s.clone(),
+              arity: 1,
+            },
+            span,
+          ));
         },
         _ => {
           // Insert implicit op
-          if tokens.last().is_some_and(|t: &Token| t.is_numeric()) {
-            tokens.push(Token::Operator {
-              op: op_set.implicit().clone(),
-              wrapping: wrappings.len(),
-            });
+          if tokens
+            .last()
+            .is_some_and(|t: &Spanned<Token<N>>| t.item.ends_operand())
+          {
+            tokens.push(implicit(span));
           }
-          tokens.push(Token::Variable(s.clone()));
+          tokens.push(Spanned::new(Token::Variable(s.clone()), span));
         },
       },
+      Lexeme::Variable(s) => {
+        if tokens
+          .last()
+          .is_some_and(|t: &Spanned<Token<N>>| t.item.ends_operand())
+        {
+          tokens.push(implicit(span));
+        }
+        tokens.push(Spanned::new(Token::Variable(s.clone()), span));
+      },
       // Insert operator
       Lexeme::Special(c) => {
         let op = match op_set.get(c) {
           Some(op) => op.clone(),
-          None => return Err(MathError::IllegalSymbol),
+          None => return Err(MathError::IllegalSymbol(span)),
         };
-        tokens.push(Token::Operator {
-          op,
-          wrapping: wrappings.len(),
-        })
+        // Prefix-only operators like `√` multiply whatever comes before them
+        if op.is_prefix_only()
+          && tokens
+            .last()
+            .is_some_and(|t: &Spanned<Token<N>>| t.item.ends_operand())
+        {
+          tokens.push(implicit(span));
+        }
+        tokens.push(Spanned::new(Token::Operator(op), span))
       },
-      // Incrememnt wrap
       Lexeme::LeftWrap(w) => {
-        // Not redundant! Makes sure wrapping level is correct
-        match tokens.last() {
-          Some(Token::Variable(_)) | Some(Token::Constant(_)) => {
-            tokens.push(Token::Operator {
-              op: op_set.implicit().clone(),
-              wrapping: wrappings.len(),
-            });
-          },
-          _ => {},
+        if tokens
+          .last()
+          .is_some_and(|t: &Spanned<Token<N>>| t.item.ends_operand())
+        {
+          tokens.push(implicit(span));
         }
-        wrappings.push(*w);
+        wrappings.push((Spanned::new(*w, span), call_pending));
+        call_pending = false;
+        tokens.push(Spanned::new(Token::Open(*w), span));
       },
       Lexeme::RightWrap(w) => {
         // Check validity of closing wrap
-        if wrappings.pop() != Some(*w) {
-          return Err(MathError::UnmatchedWrapping);
+        match wrappings.pop() {
+          Some((open, _)) if open.item == *w => {},
+          _ => return Err(MathError::UnmatchedWrapping(span)),
+        }
+        tokens.push(Spanned::new(Token::Close(*w), span));
+      },
+      // A bar closes the innermost open bar if there's an operand to
+      // close over, otherwise it opens a new one
+      Lexeme::Bar => {
+        let ends_operand = tokens
+          .last()
+          .is_some_and(|t: &Spanned<Token<N>>| t.item.ends_operand());
+        match wrappings.last() {
+          Some((open, _)) if open.item == Wrapping::Bar && ends_operand => {
+            wrappings.pop();
+            tokens.push(Spanned::new(Token::Close(Wrapping::Bar), span));
+          },
+          _ => {
+            if ends_operand {
+              tokens.push(implicit(span));
+            }
+            wrappings.push((Spanned::new(Wrapping::Bar, span), false));
+            tokens.push(Spanned::new(Token::Open(Wrapping::Bar), span));
+          },
         }
       },
-      Lexeme::Unknown(_) => return Err(MathError::IllegalSymbol),
+      // Only allowed directly inside a function call
+      Lexeme::Separator => match wrappings.last() {
+        Some((_, true)) => tokens.push(Spanned::new(Token::Separator, span)),
+        _ => return Err(MathError::MisplacedSeparator(span)),
+      },
+      Lexeme::Assign => tokens.push(Spanned::new(Token::Assign, span)),
+      // Statements can't end with wrappings left open
+      Lexeme::Terminator => match wrappings.pop() {
+        Some((open, _)) => return Err(MathError::UnmatchedWrapping(open.span)),
+        None => tokens.push(Spanned::new(Token::Terminator, span)),
+      },
+      Lexeme::Unknown(_) => return Err(MathError::IllegalSymbol(span)),
     }
   }
-  if !wrappings.is_empty() {
-    return Err(MathError::UnmatchedWrapping);
+  if let Some((open, _)) = wrappings.pop() {
+    return Err(MathError::UnmatchedWrapping(open.span));
   }
   Ok(tokens)
 }
@@ -0,0 +1,47 @@
+//! Maps the Unicode spellings of math symbols found in PDFs and web pages
+//! onto the plain ones the rest of the engine understands
+
+/// Operator symbol a Unicode operator stands for
+pub fn operator_alias(c: char) -> Option<&'static str> {
+  Some(match c {
+    '×' | '·' | '⋅' | '∙' | '∗' => "*",
+    '÷' | '∕' | '⁄' => "/",
+    '−' | '–' => "-",
+    _ => return None,
+  })
+}
+
+/// Variable name a Unicode constant stands for
+pub fn constant_alias(c: char) -> Option<&'static str> {
+  Some(match c {
+    'π' => "pi",
+    'τ' => "tau",
+    'ℯ' => "e",
+    _ => return None,
+  })
+}
+
+/// Whether `c` is a Unicode imaginary unit. These are literals like `1i`
+/// rather than the variable `i`, which can be reassigned
+pub fn is_imaginary_unit(c: char) -> bool {
+  matches!(c, 'ⅈ' | 'ⅉ')
+}
+
+/// Plain char a superscript digit or sign stands for
+pub fn superscript(c: char) -> Option<char> {
+  Some(match c {
+    '⁰' => '0',
+    '¹' => '1',
+    '²' => '2',
+    '³' => '3',
+    '⁴' => '4',
+    '⁵' => '5',
+    '⁶' => '6',
+    '⁷' => '7',
+    '⁸' => '8',
+    '⁹' => '9',
+    '⁺' => '+',
+    '⁻' => '-',
+    _ => return None,
+  })
+}
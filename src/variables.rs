@@ -1,26 +1,30 @@
-use std::{collections::HashMap, f64::consts::*};
+use std::collections::HashMap;
 
-use crate::number::Number;
+use crate::number::{Number, Numeric};
 
 #[derive(Clone, Copy, Debug)]
-pub struct Variable(pub Number);
+pub struct Variable<N = Number>(pub N);
 
 impl Variable {
   pub const fn new(real: f64, imaginary: f64) -> Self {
     Self(Number::new(real, imaginary))
   }
 
+  /// [Variable::builtins] for [Number]
   pub fn default_set() -> HashMap<String, Variable> {
+    Self::builtins()
+  }
+}
+
+impl<N: Numeric> Variable<N> {
+  /// Built in constants, leaving out any the backend can't represent
+  pub fn builtins() -> HashMap<String, Variable<N>> {
     let mut map = HashMap::new();
-    map.insert("pi".to_string(), _PI_VAR);
-    map.insert("e".to_string(), _E_VAR);
-    map.insert("tau".to_string(), _TAU_VAR);
-    map.insert("i".to_string(), _I_VAR);
+    for name in ["pi", "e", "tau", "i"] {
+      if let Some(value) = N::constant(name) {
+        map.insert(name.to_string(), Variable(value));
+      }
+    }
     map
   }
 }
-
-const _PI_VAR: Variable = Variable::new(PI, 0.0);
-const _E_VAR: Variable = Variable::new(E, 0.0);
-const _TAU_VAR: Variable = Variable::new(TAU, 0.0);
-const _I_VAR: Variable = Variable::new(0.0, 1.0);
@@ -0,0 +1,378 @@
+//! Traversals over [AstNode] trees, so tools built on the parser don't have
+//! to match on every node kind and recurse by hand
+
+use crate::{
+  lexer::Wrapping,
+  number::{Number, Numeric},
+  operator::Operation,
+  parse::{AstNode, Fixity},
+  span::Span,
+};
+
+/// Computes a value from a tree. Each method gets the parts of one node kind
+/// and decides whether and when to visit its children, with
+/// [AstNode::accept]. See [Walker] for visitors that always visit them all
+pub trait Visitor<N: Numeric = Number> {
+  type Output;
+
+  fn visit_constant(&mut self, value: N, span: Span) -> Self::Output;
+
+  fn visit_variable(&mut self, name: &str, span: Span) -> Self::Output;
+
+  fn visit_call(
+    &mut self,
+    name: &str,
+    args: &[AstNode<N>],
+    span: Span,
+  ) -> Self::Output;
+
+  fn visit_unary(
+    &mut self,
+    op: &Operation<N>,
+    fixity: Fixity,
+    operand: &AstNode<N>,
+    span: Span,
+  ) -> Self::Output;
+
+  fn visit_binary(
+    &mut self,
+    op: &Operation<N>,
+    left: &AstNode<N>,
+    right: &AstNode<N>,
+    span: Span,
+  ) -> Self::Output;
+
+  /// Absolute value bars are groups too, so this can't just skip ahead to
+  /// `inner`
+  fn visit_group(
+    &mut self,
+    wrapping: Wrapping,
+    inner: &AstNode<N>,
+    span: Span,
+  ) -> Self::Output;
+}
+
+/// Like [Visitor], but walks into every child on its own with
+/// [AstNode::walk], so implementations only override the node kinds they care
+/// about. Nodes without children give the default output, and the outputs of
+/// children are put together with [Walker::merge]
+pub trait Walker<N: Numeric = Number> {
+  type Output: Default;
+
+  /// Keeps the later output by default, which is enough when the walker only
+  /// collects into its own fields
+  fn merge(
+    &mut self,
+    _first: Self::Output,
+    later: Self::Output,
+  ) -> Self::Output {
+    later
+  }
+
+  fn visit_constant(&mut self, _value: N, _span: Span) -> Self::Output {
+    Self::Output::default()
+  }
+
+  fn visit_variable(&mut self, _name: &str, _span: Span) -> Self::Output {
+    Self::Output::default()
+  }
+
+  fn visit_call(
+    &mut self,
+    _name: &str,
+    args: &[AstNode<N>],
+    _span: Span,
+  ) -> Self::Output {
+    let mut output = Self::Output::default();
+    for arg in args {
+      let next = arg.walk(self);
+      output = self.merge(output, next);
+    }
+    output
+  }
+
+  fn visit_unary(
+    &mut self,
+    _op: &Operation<N>,
+    _fixity: Fixity,
+    operand: &AstNode<N>,
+    _span: Span,
+  ) -> Self::Output {
+    operand.walk(self)
+  }
+
+  fn visit_binary(
+    &mut self,
+    _op: &Operation<N>,
+    left: &AstNode<N>,
+    right: &AstNode<N>,
+    _span: Span,
+  ) -> Self::Output {
+    let left = left.walk(self);
+    let right = right.walk(self);
+    self.merge(left, right)
+  }
+
+  fn visit_group(
+    &mut self,
+    _wrapping: Wrapping,
+    inner: &AstNode<N>,
+    _span: Span,
+  ) -> Self::Output {
+    inner.walk(self)
+  }
+}
+
+/// Changes a tree in place. Every method walks into the children by default,
+/// so implementations only override the node kinds they care about
+pub trait VisitorMut<N: Numeric = Number> {
+  /// Called for every node. Override it to replace whole nodes, calling
+  /// [walk_mut] to keep going into the children
+  fn visit_node_mut(&mut self, node: &mut AstNode<N>) {
+    walk_mut(self, node);
+  }
+
+  fn visit_constant_mut(&mut self, _value: &mut N, _span: Span) {}
+
+  fn visit_variable_mut(&mut self, _name: &mut String, _span: Span) {}
+
+  fn visit_call_mut(
+    &mut self,
+    _name: &mut String,
+    args: &mut [AstNode<N>],
+    _span: Span,
+  ) {
+    for arg in args {
+      self.visit_node_mut(arg);
+    }
+  }
+
+  fn visit_unary_mut(
+    &mut self,
+    _op: &mut Operation<N>,
+    _fixity: Fixity,
+    operand: &mut AstNode<N>,
+    _span: Span,
+  ) {
+    self.visit_node_mut(operand);
+  }
+
+  fn visit_binary_mut(
+    &mut self,
+    _op: &mut Operation<N>,
+    left: &mut AstNode<N>,
+    right: &mut AstNode<N>,
+    _span: Span,
+  ) {
+    self.visit_node_mut(left);
+    self.visit_node_mut(right);
+  }
+
+  fn visit_group_mut(
+    &mut self,
+    _wrapping: Wrapping,
+    inner: &mut AstNode<N>,
+    _span: Span,
+  ) {
+    self.visit_node_mut(inner);
+  }
+}
+
+/// Hands `node` to the [VisitorMut] method for its kind
+pub fn walk_mut<N: Numeric, V: VisitorMut<N> + ?Sized>(
+  visitor: &mut V,
+  node: &mut AstNode<N>,
+) {
+  match node {
+    AstNode::Constant { value, span } => {
+      visitor.visit_constant_mut(value, *span)
+    },
+    AstNode::Variable { name, span } => visitor.visit_variable_mut(name, *span),
+    AstNode::Call { name, args, span } => {
+      visitor.visit_call_mut(name, args, *span)
+    },
+    AstNode::Unary {
+      op,
+      fixity,
+      operand,
+      span,
+    } => visitor.visit_unary_mut(op, *fixity, operand, *span),
+    AstNode::Binary {
+      op,
+      left,
+      right,
+      span,
+    } => visitor.visit_binary_mut(op, left, right, *span),
+    AstNode::Group {
+      wrapping,
+      inner,
+      span,
+    } => visitor.visit_group_mut(*wrapping, inner, *span),
+  }
+}
+
+/// Rebuilds a tree bottom up. Children are folded before their parent's
+/// method sees them, and every method rebuilds the node unchanged by default
+pub trait Fold<N: Numeric = Number> {
+  fn fold_constant(&mut self, value: N, span: Span) -> AstNode<N> {
+    AstNode::Constant { value, span }
+  }
+
+  fn fold_variable(&mut self, name: String, span: Span) -> AstNode<N> {
+    AstNode::Variable { name, span }
+  }
+
+  fn fold_call(
+    &mut self,
+    name: String,
+    args: Vec<AstNode<N>>,
+    span: Span,
+  ) -> AstNode<N> {
+    AstNode::Call { name, args, span }
+  }
+
+  fn fold_unary(
+    &mut self,
+    op: Operation<N>,
+    fixity: Fixity,
+    operand: AstNode<N>,
+    span: Span,
+  ) -> AstNode<N> {
+    AstNode::Unary {
+      op,
+      fixity,
+      operand: Box::new(operand),
+      span,
+    }
+  }
+
+  fn fold_binary(
+    &mut self,
+    op: Operation<N>,
+    left: AstNode<N>,
+    right: AstNode<N>,
+    span: Span,
+  ) -> AstNode<N> {
+    AstNode::Binary {
+      op,
+      left: Box::new(left),
+      right: Box::new(right),
+      span,
+    }
+  }
+
+  fn fold_group(
+    &mut self,
+    wrapping: Wrapping,
+    inner: AstNode<N>,
+    span: Span,
+  ) -> AstNode<N> {
+    AstNode::Group {
+      wrapping,
+      inner: Box::new(inner),
+      span,
+    }
+  }
+}
+
+impl<N: Numeric> AstNode<N> {
+  pub fn accept<V: Visitor<N> + ?Sized>(&self, visitor: &mut V) -> V::Output {
+    match self {
+      AstNode::Constant { value, span } => {
+        visitor.visit_constant(value.clone(), *span)
+      },
+      AstNode::Variable { name, span } => visitor.visit_variable(name, *span),
+      AstNode::Call { name, args, span } => {
+        visitor.visit_call(name, args, *span)
+      },
+      AstNode::Unary {
+        op,
+        fixity,
+        operand,
+        span,
+      } => visitor.visit_unary(op, *fixity, operand, *span),
+      AstNode::Binary {
+        op,
+        left,
+        right,
+        span,
+      } => visitor.visit_binary(op, left, right, *span),
+      AstNode::Group {
+        wrapping,
+        inner,
+        span,
+      } => visitor.visit_group(*wrapping, inner, *span),
+    }
+  }
+
+  pub fn walk<W: Walker<N> + ?Sized>(&self, walker: &mut W) -> W::Output {
+    match self {
+      AstNode::Constant { value, span } => {
+        walker.visit_constant(value.clone(), *span)
+      },
+      AstNode::Variable { name, span } => walker.visit_variable(name, *span),
+      AstNode::Call { name, args, span } => {
+        walker.visit_call(name, args, *span)
+      },
+      AstNode::Unary {
+        op,
+        fixity,
+        operand,
+        span,
+      } => walker.visit_unary(op, *fixity, operand, *span),
+      AstNode::Binary {
+        op,
+        left,
+        right,
+        span,
+      } => walker.visit_binary(op, left, right, *span),
+      AstNode::Group {
+        wrapping,
+        inner,
+        span,
+      } => walker.visit_group(*wrapping, inner, *span),
+    }
+  }
+
+  pub fn accept_mut<V: VisitorMut<N> + ?Sized>(&mut self, visitor: &mut V) {
+    visitor.visit_node_mut(self);
+  }
+
+  pub fn fold<F: Fold<N> + ?Sized>(self, folder: &mut F) -> AstNode<N> {
+    match self {
+      AstNode::Constant { value, span } => folder.fold_constant(value, span),
+      AstNode::Variable { name, span } => folder.fold_variable(name, span),
+      AstNode::Call { name, args, span } => {
+        let args = args.into_iter().map(|arg| arg.fold(folder)).collect();
+        folder.fold_call(name, args, span)
+      },
+      AstNode::Unary {
+        op,
+        fixity,
+        operand,
+        span,
+      } => {
+        let operand = operand.fold(folder);
+        folder.fold_unary(op, fixity, operand, span)
+      },
+      AstNode::Binary {
+        op,
+        left,
+        right,
+        span,
+      } => {
+        let left = left.fold(folder);
+        let right = right.fold(folder);
+        folder.fold_binary(op, left, right, span)
+      },
+      AstNode::Group {
+        wrapping,
+        inner,
+        span,
+      } => {
+        let inner = inner.fold(folder);
+        folder.fold_group(wrapping, inner, span)
+      },
+    }
+  }
+}
@@ -0,0 +1,38 @@
+use math::{error::MathError, evaluate};
+
+fn eval(input: &str) -> Result<f64, MathError> {
+  evaluate(input.to_string()).map(|n| n.re)
+}
+
+#[test]
+fn bars_take_the_magnitude() {
+  assert_eq!(eval("|2 - 5|").unwrap(), 3.0);
+  assert_eq!(eval("|3 + 4i|").unwrap(), 5.0);
+  assert_eq!(eval("2|-3| + 1").unwrap(), 7.0);
+  assert_eq!(eval("|-2| |-3|").unwrap(), 6.0);
+  assert_eq!(eval("|-3|!").unwrap(), 6.0);
+}
+
+#[test]
+fn nested_bars() {
+  assert_eq!(eval("||-2| - |5||").unwrap(), 3.0);
+  assert_eq!(eval("|1 - |-4||").unwrap(), 3.0);
+  assert_eq!(eval("|(|-1| - 3)|").unwrap(), 2.0);
+}
+
+#[test]
+fn unbalanced_bars() {
+  assert!(matches!(
+    eval("|2 - 5"),
+    Err(MathError::UnmatchedWrapping(_))
+  ));
+  assert!(matches!(
+    eval("2 - 5|"),
+    Err(MathError::UnmatchedWrapping(_))
+  ));
+  assert!(matches!(
+    eval("(|2)|"),
+    Err(MathError::UnmatchedWrapping(_))
+  ));
+  assert!(matches!(eval("||"), Err(MathError::UnmatchedWrapping(_))));
+}
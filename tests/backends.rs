@@ -0,0 +1,73 @@
+mod common;
+
+use common::run_program;
+use math::{
+  context::Context, error::MathError, evaluate, evaluate_as, number::Number,
+};
+
+fn real(input: &str) -> Result<f64, MathError> {
+  evaluate_as::<f64>(input.to_string())
+}
+
+#[test]
+fn real_backend_evaluates() {
+  assert_eq!(real("2^10 + sqrt(16)").unwrap(), 1028.0);
+  assert_eq!(real("200 + 10%").unwrap(), 220.0);
+  assert_eq!(real("max(3, -1, 7) - min(2, 5)").unwrap(), 5.0);
+  assert_eq!(real("log(2, 8)").unwrap(), 3.0);
+  assert_eq!(real("|-3| * 5!").unwrap(), 360.0);
+  assert_eq!(real("(-2)^3").unwrap(), -8.0);
+  assert!((real("sin(pi / 6)").unwrap() - 0.5).abs() < 1e-12);
+  assert!((real("gamma(0.5)^2").unwrap() - std::f64::consts::PI).abs() < 1e-12);
+}
+
+#[test]
+fn real_backend_rejects_complex_numbers() {
+  for input in ["sqrt(-1)", "(-8)^(1/3)", "ln(-1)", "arcsin(2)", "√-4"] {
+    assert!(
+      matches!(real(input), Err(MathError::ComplexResult(_))),
+      "{input}"
+    );
+  }
+  assert!(matches!(real("2i"), Err(MathError::BadNumber(_))));
+  assert!(matches!(real("i"), Err(MathError::UndefinedVariable(..))));
+  assert!(matches!(real("1/0"), Err(MathError::DivisionByZero(_))));
+}
+
+#[test]
+fn complex_backend_is_the_default() {
+  for input in [
+    "2^10 + sqrt(16)",
+    "sqrt(-4)",
+    "(1 + 2i) * i",
+    "cot(1) + csc(2)",
+  ] {
+    let generic = evaluate_as::<Number>(input.to_string()).unwrap();
+    let default = evaluate(input.to_string()).unwrap();
+    assert!((generic - default).norm() < 1e-9, "{input}");
+  }
+}
+
+#[test]
+fn real_backend_runs_programs() {
+  let results = run_program::<f64>("r = 2; f(x) = pi x^2; f(r)");
+  assert_eq!(results[0], Some(2.0));
+  assert_eq!(results[1], None);
+  assert_eq!(results[2], Some(4.0 * std::f64::consts::PI));
+}
+
+#[test]
+fn contexts_are_built_for_any_backend() {
+  // No annotations needed for the default backend
+  let mut ctx = Context::default();
+  ctx.assign_variable("x".to_string(), Number::new(1.0, 2.0));
+  assert_eq!(ctx.read_variable("x".to_string()).unwrap().im, 2.0);
+  assert!(Context::new().read_variable("pi".to_string()).is_err());
+
+  let real = Context::<f64>::with_builtins();
+  let pi = real.read_variable("pi".to_string()).unwrap();
+  assert_eq!(pi, std::f64::consts::PI);
+  let root = real.compute_function("sqrt".to_string(), &[9.0]).unwrap();
+  assert_eq!(root, 3.0);
+  assert!(Context::<f64>::empty().function("sqrt").is_none());
+}
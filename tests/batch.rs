@@ -0,0 +1,119 @@
+use math::{
+  bytecode::Bytecode, context::Context, error::MathError,
+  expression::Expression, number::Number,
+};
+
+fn compile(input: &str, ctx: &Context) -> Bytecode {
+  Bytecode::compile(&Expression::new(input).unwrap(), ctx).unwrap()
+}
+
+fn buffer<T: Default>(rows: usize) -> Vec<Result<T, MathError>> {
+  (0..rows).map(|_| Ok(T::default())).collect()
+}
+
+#[test]
+fn errors_are_per_row() {
+  let ctx = Context::default();
+  let code = compile("1/(x-3)", &ctx);
+  let xs: Vec<_> = [1.0, 2.0, 3.0, 4.0].map(|x| Number::new(x, 0.0)).into();
+  let mut out = buffer(xs.len());
+  code.run_batch(&ctx, &[&xs], &mut out);
+  assert_eq!(out[0].as_ref().unwrap(), &Number::new(-0.5, 0.0));
+  assert_eq!(out[1].as_ref().unwrap(), &Number::new(-1.0, 0.0));
+  assert!(matches!(out[2], Err(MathError::DivisionByZero(_))));
+  assert_eq!(out[3].as_ref().unwrap(), &Number::new(1.0, 0.0));
+
+  let reals = [1.0, 2.0, 3.0, 4.0];
+  let mut out = buffer(reals.len());
+  code.run_batch_real(&ctx, &[&reals], &mut out);
+  assert_eq!(*out[0].as_ref().unwrap(), -0.5);
+  assert!(matches!(out[2], Err(MathError::DivisionByZero(_))));
+  assert_eq!(*out[3].as_ref().unwrap(), 1.0);
+}
+
+#[test]
+fn real_path_agrees_with_complex_path() {
+  let ctx = Context::default();
+  let xs: Vec<f64> = (0..50).map(|n| n as f64 * 0.37 - 9.0).collect();
+  let ys: Vec<f64> = (0..50).map(|n| n as f64 * 0.11 + 0.5).collect();
+  let complex_xs: Vec<_> = xs.iter().map(|x| Number::new(*x, 0.0)).collect();
+  let complex_ys: Vec<_> = ys.iter().map(|y| Number::new(*y, 0.0)).collect();
+  for input in [
+    "x^2 + 3x*y - 7",
+    "-x^3 + √y / x",
+    "sin(x) * cos(y) + sqrt(x^2 + y^2)",
+    "|x - y| + 10%",
+    "x/y - y^x",
+  ] {
+    let code = compile(input, &ctx);
+    let mut complex = buffer(xs.len());
+    let mut real = buffer(xs.len());
+    code.run_batch(&ctx, &[&complex_xs, &complex_ys], &mut complex);
+    code.run_batch_real(&ctx, &[&xs, &ys], &mut real);
+    for (n, (complex, real)) in complex.iter().zip(&real).enumerate() {
+      let complex = complex.as_ref().unwrap();
+      let real = real.as_ref().unwrap_or_else(|err| {
+        panic!("{input} at row {n}: {err:?} vs {complex}")
+      });
+      assert_eq!(complex.im, 0.0, "{input} at row {n}");
+      let tolerance = 1e-12 * complex.re.abs().max(1.0);
+      assert!(
+        (complex.re - real).abs() <= tolerance,
+        "{input} at row {n}: {complex} vs {real}"
+      );
+    }
+  }
+}
+
+#[test]
+fn falls_back_to_complex_numbers() {
+  let ctx = Context::default();
+  let code = compile("sqrt(x)^2", &ctx);
+  let mut out = buffer(2);
+  code.run_batch_real(&ctx, &[&[4.0, -4.0]], &mut out);
+  assert_eq!(*out[0].as_ref().unwrap(), 4.0);
+  assert_eq!(*out[1].as_ref().unwrap(), -4.0);
+  // The rounding error grows with the values
+  let mut out = buffer(1);
+  code.run_batch_real(&ctx, &[&[-4e20]], &mut out);
+  assert!((out[0].as_ref().unwrap() / -4e20 - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn complex_results_are_errors() {
+  let ctx = Context::default();
+  let code = compile("sqrt(x)", &ctx);
+  let mut out = buffer(2);
+  code.run_batch_real(&ctx, &[&[9.0, -9.0]], &mut out);
+  assert_eq!(*out[0].as_ref().unwrap(), 3.0);
+  assert!(matches!(out[1], Err(MathError::ComplexResult(_))));
+}
+
+#[test]
+fn large_real_parts_keep_their_imaginary_part() {
+  let ctx = Context::default();
+  let code = compile("x + sqrt(-1)", &ctx);
+  let mut out = buffer(2);
+  code.run_batch_real(&ctx, &[&[1.0, 1e13]], &mut out);
+  assert!(matches!(out[0], Err(MathError::ComplexResult(_))));
+  assert!(matches!(out[1], Err(MathError::ComplexResult(_))));
+}
+
+#[test]
+fn small_values_keep_their_imaginary_part() {
+  let ctx = Context::default();
+  let code = compile("x * sqrt(-1)", &ctx);
+  let mut out = buffer(2);
+  code.run_batch_real(&ctx, &[&[1e-13, 1e-12]], &mut out);
+  assert!(matches!(out[0], Err(MathError::ComplexResult(_))));
+  assert!(matches!(out[1], Err(MathError::ComplexResult(_))));
+}
+
+#[test]
+#[should_panic]
+fn short_columns_panic() {
+  let ctx = Context::default();
+  let code = compile("x + y", &ctx);
+  let mut out = buffer(3);
+  code.run_batch_real(&ctx, &[&[1.0, 2.0, 3.0], &[1.0]], &mut out);
+}
@@ -0,0 +1,100 @@
+use math::{
+  bytecode::{Bytecode, Instruction},
+  context::Context,
+  error::MathError,
+  evaluate_in,
+  expression::Expression,
+  number::Number,
+};
+
+/// Runs `input` both ways and checks they agree
+fn run(
+  input: &str,
+  ctx: &Context,
+  values: &[f64],
+) -> Result<Number, MathError> {
+  let expression = Expression::new(input).unwrap();
+  let values: Vec<_> = values.iter().map(|v| Number::new(*v, 0.0)).collect();
+  let tree = expression.evaluate_with(ctx, &values);
+  let vm =
+    Bytecode::compile(&expression, ctx).and_then(|code| code.run(ctx, &values));
+  match (&tree, &vm) {
+    (Ok(a), Ok(b)) => assert_eq!(a, b, "{input}"),
+    (Err(a), Err(b)) => assert_eq!(a.to_string(), b.to_string(), "{input}"),
+    _ => panic!("{input}: tree gave {tree:?}, vm gave {vm:?}"),
+  }
+  vm
+}
+
+#[test]
+fn matches_the_tree_walker() {
+  let ctx = Context::default();
+  let cases = [
+    "x^2 + 3x - 7",
+    "-x^2 + √y",
+    "sin(x) * cos(y) + sqrt(x^2 + y^2)",
+    "|x - y| / (1 + e^-(x y))",
+    "200 + x%",
+    "x! + max(x, y, 2) * log(2, y)",
+    "pi tau i",
+  ];
+  for input in cases {
+    run(input, &ctx, &[3.0, 4.0]).unwrap();
+  }
+}
+
+#[test]
+fn pools_constants_and_resolves_slots() {
+  let ctx = Context::default();
+  let expression = Expression::new("2x + 2y + pi").unwrap();
+  let code = Bytecode::compile(&expression, &ctx).unwrap();
+  assert_eq!(code.inputs(), ["x", "y"]);
+  let constants = code
+    .instructions()
+    .iter()
+    .filter_map(|i| match i {
+      Instruction::Constant(index) => Some(*index),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(constants, [0, 0, 1]);
+}
+
+#[test]
+fn errors_match_the_tree_walker() {
+  let mut ctx = Context::default();
+  assert!(matches!(
+    run("1 / (x - 3)", &ctx, &[3.0]),
+    Err(MathError::DivisionByZero(_))
+  ));
+  assert!(matches!(
+    run("x + y", &ctx, &[1.0]),
+    Err(MathError::UndefinedVariable(..))
+  ));
+  assert!(matches!(
+    run("nope(x)", &ctx, &[1.0]),
+    Err(MathError::UndefinedFunction(..))
+  ));
+  assert!(matches!(
+    run("sin(x, x)", &ctx, &[1.0]),
+    Err(MathError::ArgumentCount(..))
+  ));
+  evaluate_in("f(t) = t^2 + k".to_string(), &mut ctx).unwrap();
+  evaluate_in("k = 1".to_string(), &mut ctx).unwrap();
+  assert_eq!(run("f(x) + 1", &ctx, &[2.0]).unwrap().re, 6.0);
+}
+
+#[test]
+fn reads_unbound_inputs_from_the_context() {
+  let mut ctx = Context::default();
+  evaluate_in("a = 5".to_string(), &mut ctx).unwrap();
+  evaluate_in("b = 2i".to_string(), &mut ctx).unwrap();
+  assert_eq!(run("a + 1", &ctx, &[]).unwrap().re, 6.0);
+  assert!(matches!(
+    run("a * x + b", &ctx, &[]),
+    Err(MathError::UndefinedVariable(..))
+  ));
+  // Values given for an input still win over the context
+  assert_eq!(run("a - b", &ctx, &[1.0]).unwrap(), Number::new(1.0, -2.0));
+  assert_eq!(run("a - b", &ctx, &[1.0, 0.5]).unwrap().re, 0.5);
+}
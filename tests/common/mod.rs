@@ -0,0 +1,50 @@
+//! Fixtures shared by the integration tests, each of which only uses some of
+//! them
+#![allow(dead_code)]
+
+use math::{
+  context::Context,
+  latex,
+  lexer::{self, Lexeme},
+  number::Numeric,
+  operator::OperatorSet,
+  parse::{Ast, Program},
+  span::Spanned,
+  token::{self, Token},
+};
+
+/// Lexemes of `input` with the default operators, without their spans
+pub fn lexemes(input: &str) -> Vec<Lexeme> {
+  let op_set = OperatorSet::default();
+  lexer::lex(input.to_string(), &op_set)
+    .into_iter()
+    .map(|l| l.item)
+    .collect()
+}
+
+/// Tokens of `input` for any backend
+pub fn tokens<N: Numeric>(input: &str) -> Vec<Spanned<Token<N>>> {
+  let op_set = OperatorSet::<N>::standard();
+  token::tokenize(&op_set, lexer::lex(input.to_string(), &op_set)).unwrap()
+}
+
+/// Tree of `input`, which has to be well formed
+pub fn parse(input: &str) -> Ast {
+  Ast::new(tokens(input)).unwrap()
+}
+
+/// Tree of LaTeX `input`, which has to be well formed
+pub fn parse_latex(input: &str) -> Ast {
+  let op_set = OperatorSet::default();
+  let lexemes = latex::lex(input.to_string(), &op_set);
+  Ast::new(token::tokenize(&op_set, lexemes).unwrap()).unwrap()
+}
+
+/// Results of running the statements in `input` against a fresh context
+pub fn run_program<N: Numeric>(input: &str) -> Vec<Option<N>> {
+  let op_set = OperatorSet::<N>::standard();
+  let lexemes = lexer::lex_program(input.to_string(), &op_set);
+  let tokens = token::tokenize(&op_set, lexemes).unwrap();
+  let mut ctx = Context::<N>::with_builtins();
+  Program::new(tokens).unwrap().execute(&mut ctx).unwrap()
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use common::run_program;
+use math::{
+  decimal::Decimal, error::MathError, evaluate_as, number::result_to_string,
+};
+
+type Digits50 = Decimal<50>;
+
+fn precise(input: &str) -> Result<Digits50, MathError> {
+  evaluate_as::<Digits50>(input.to_string())
+}
+
+fn shown(input: &str) -> String {
+  result_to_string(precise(input))
+}
+
+// Expected values from mpmath, rounded to 50 places
+#[test]
+fn arithmetic_is_precise() {
+  assert_eq!(
+    shown("1/3"),
+    "0.33333333333333333333333333333333333333333333333333"
+  );
+  assert_eq!(
+    shown("-2/3"),
+    "-0.66666666666666666666666666666666666666666666666667"
+  );
+  assert_eq!(shown("0.1 + 0.2"), "0.3");
+  assert_eq!(shown("1.1^2"), "1.21");
+  assert_eq!(shown("2^100"), "1267650600228229401496703205376");
+  assert_eq!(shown("2^-3"), "0.125");
+  assert_eq!(shown("25!"), "15511210043330985984000000");
+  assert_eq!(shown("200 + 10%"), "220");
+  assert_eq!(shown("max(1/3, 0.3) - 1/3"), "0");
+}
+
+#[test]
+fn powers_and_roots_are_precise() {
+  let root = "1.41421356237309504880168872420969807856967187537695";
+  assert_eq!(shown("sqrt(2)"), root);
+  assert_eq!(shown("2^0.5"), root);
+  assert_eq!(shown("sqrt(2)^2"), "2");
+  assert_eq!(
+    shown("e"),
+    "2.71828182845904523536028747135266249775724709369996"
+  );
+  assert_eq!(shown("e^1"), shown("e"));
+  assert_eq!(
+    shown("e^10"),
+    "22026.46579480671651695790064528424436635351261855678107"
+  );
+  assert_eq!(
+    shown("e^-20"),
+    "0.00000000206115362243855782796594038015582097637581"
+  );
+  assert_eq!(
+    shown("ln(2)"),
+    "0.69314718055994530941723212145817656807550013436026"
+  );
+  assert_eq!(
+    shown("ln(10)"),
+    "2.30258509299404568401799145468436420760110148862877"
+  );
+  assert_eq!(shown("ln(e^3)"), "3");
+}
+
+#[test]
+fn large_and_small_results_are_precise() {
+  assert_eq!(
+    shown("e^100"),
+    "26881171418161354484126255515800135873611118.77374192241519160861528028703490956491415887109722"
+  );
+  assert_eq!(shown("exp(100)"), shown("e^100"));
+  assert_eq!(
+    shown("2^100.5"),
+    "1792728671193156477399422023278.66149639423922256427368802583379766129992073960271"
+  );
+  assert_eq!(
+    shown("3^50.5"),
+    "1243435789333745207971490.00407809988777135686720911848017909705693676041164"
+  );
+  assert_eq!(
+    shown("1.5^200"),
+    "165291991078820803015600259355571011.18746112880605089770800296398286116527930567260536"
+  );
+  assert_eq!(shown("(10^-30)^-3"), format!("1{}", "0".repeat(90)));
+  // Only the places count, not significant digits
+  assert_eq!(
+    shown("e^-100"),
+    "0.00000000000000000000000000000000000000000003720076"
+  );
+  assert_eq!(shown("e^-(10^10)"), "0");
+  // Constants are worked out to as many places as the power needs
+  assert_eq!(
+    shown("pi^100"),
+    "51878483143196131920862615246303013562686760680405.78499007184235808218814439170444889750694577840234"
+  );
+  assert_eq!(
+    shown("tau^20"),
+    "9195966217409212.68443782116142365756994524487058240280295740199196"
+  );
+}
+
+#[test]
+fn trig_functions_are_precise() {
+  assert_eq!(
+    shown("pi"),
+    "3.14159265358979323846264338327950288419716939937511"
+  );
+  assert_eq!(
+    shown("sin(1)"),
+    "0.84147098480789650665250232163029899962256306079837"
+  );
+  assert_eq!(
+    shown("cos(1)"),
+    "0.54030230586813971740093660744297660373231042061792"
+  );
+  assert_eq!(
+    shown("tan(1)"),
+    "1.55740772465490223050697480745836017308725077238152"
+  );
+  assert_eq!(
+    shown("sin(100)"),
+    "-0.50636564110975879365655761045978543206503272129066"
+  );
+  assert_eq!(shown("sin(pi / 6)"), "0.5");
+  assert_eq!(shown("cos(pi)"), "-1");
+  assert_eq!(
+    shown("arcsin(0.5)"),
+    "0.52359877559829887307710723054658381403286156656252"
+  );
+  assert_eq!(
+    shown("arccos(0.3)"),
+    "1.26610367277949911125931873041222227514402466798078"
+  );
+  assert_eq!(
+    shown("arctan(2)"),
+    "1.10714871779409050301706546017853704007004764540143"
+  );
+  assert_eq!(shown("4 arctan(1)"), shown("pi"));
+  assert_eq!(
+    shown("tan(1.5707963)"),
+    "37320539.58671654132004064246540849411206645634631614972074"
+  );
+}
+
+#[test]
+fn digits_are_chosen_by_the_caller() {
+  let third = evaluate_as::<Decimal<5>>("1/3".to_string()).unwrap();
+  assert_eq!(third.to_string(), "0.33333");
+  let pi = evaluate_as::<Decimal<100>>("pi".to_string()).unwrap();
+  assert_eq!(
+        pi.to_string(),
+        "3.141592653589793238462643383279502884197169399375105820974944592307816406286208998628034825342117068"
+    );
+}
+
+#[test]
+fn errors_match_the_real_line() {
+  for input in ["sqrt(-1)", "ln(-1)", "(-2)^0.5", "arcsin(2)"] {
+    assert!(
+      matches!(precise(input), Err(MathError::ComplexResult(_))),
+      "{input}"
+    );
+  }
+  assert!(matches!(precise("1/0"), Err(MathError::DivisionByZero(_))));
+  assert!(matches!(precise("0^-1"), Err(MathError::DivisionByZero(_))));
+  assert!(matches!(precise("2^(10^10)"), Err(MathError::TooBig(_))));
+  assert!(matches!(precise("e^(10^10)"), Err(MathError::TooBig(_))));
+  assert!(matches!(precise("0.5^-(2^30)"), Err(MathError::TooBig(_))));
+  assert!(matches!(
+    precise("tan(pi / 2)"),
+    Err(MathError::DivisionByZero(_))
+  ));
+  assert!(matches!(precise("2i"), Err(MathError::BadNumber(_))));
+}
+
+#[test]
+fn programs_run_precisely() {
+  let results = run_program::<Digits50>("a = 1/7; f(x) = x^2 + a; f(2)");
+  assert_eq!(
+    results[2].as_ref().unwrap().to_string(),
+    "4.14285714285714285714285714285714285714285714285714"
+  );
+}
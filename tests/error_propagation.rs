@@ -0,0 +1,70 @@
+use math::{error::MathError, evaluate, span::Span};
+
+fn eval(input: &str) -> Result<f64, MathError> {
+  evaluate(input.to_string()).map(|n| n.re)
+}
+
+#[test]
+fn undefined_variable_is_not_treated_as_unary() {
+  assert!(matches!(
+    eval("unknown - 3"),
+    Err(MathError::UndefinedVariable(name, span))
+      if name == "unknown" && span == Span::new(0, 7)
+  ));
+  assert!(matches!(
+    eval("3 - foo"),
+    Err(MathError::UndefinedVariable(name, _)) if name == "foo"
+  ));
+  assert!(matches!(
+    eval("2 * (3 + foo)"),
+    Err(MathError::UndefinedVariable(name, span))
+      if name == "foo" && span == Span::new(9, 12)
+  ));
+}
+
+#[test]
+fn division_by_zero_propagates() {
+  assert!(matches!(eval("1/0 + 5"), Err(MathError::DivisionByZero(_))));
+  assert!(matches!(eval("5 + 1/0"), Err(MathError::DivisionByZero(_))));
+  assert!(matches!(eval("5 - 1/0"), Err(MathError::DivisionByZero(_))));
+  assert!(matches!(eval("-(1/0)"), Err(MathError::DivisionByZero(_))));
+  assert!(matches!(eval("(1/0)!"), Err(MathError::DivisionByZero(_))));
+  assert!(matches!(
+    eval("1/0 + foo"),
+    Err(MathError::DivisionByZero(span)) if span == Span::new(0, 3)
+  ));
+}
+
+#[test]
+fn function_argument_errors_propagate() {
+  assert!(matches!(
+    eval("sin(foo) + 1"),
+    Err(MathError::UndefinedVariable(name, _)) if name == "foo"
+  ));
+  assert!(matches!(
+    eval("max(1, 2/0)"),
+    Err(MathError::DivisionByZero(_))
+  ));
+  assert!(matches!(
+    eval("1 - nope(2)"),
+    Err(MathError::UndefinedFunction(name, _)) if name == "nope"
+  ));
+}
+
+#[test]
+fn missing_operands_are_syntax_errors() {
+  assert!(matches!(eval("5 -"), Err(MathError::MissingOperand(_))));
+  assert!(matches!(eval("* 5"), Err(MathError::MissingOperand(_))));
+  assert!(matches!(eval("1 + * 2"), Err(MathError::MissingOperand(_))));
+  assert!(matches!(eval("()"), Err(MathError::MissingOperand(_))));
+}
+
+#[test]
+fn real_unary_uses_still_work() {
+  assert_eq!(eval("-3").unwrap(), -3.0);
+  assert_eq!(eval("2 - -3").unwrap(), 5.0);
+  assert_eq!(eval("--2").unwrap(), 2.0);
+  assert_eq!(eval("-2^2").unwrap(), -4.0);
+  assert_eq!(eval("2^-1").unwrap(), 0.5);
+  assert_eq!(eval("3!").unwrap(), 6.0);
+}
@@ -0,0 +1,44 @@
+use math::{
+  context::Context, error::MathError, evaluate_in, expression::Expression,
+  number::Number,
+};
+
+#[test]
+fn lists_free_variables_in_order() {
+  let expr: Expression = "a x^2 + b x + c + pi".parse().unwrap();
+  assert_eq!(expr.variables(), ["a", "x", "b", "c"]);
+  let expr = Expression::new("sin(2 pi) + e").unwrap();
+  assert!(expr.variables().is_empty());
+}
+
+#[test]
+fn evaluates_with_bound_values() {
+  let expr = Expression::new("x^2 + y").unwrap();
+  let ctx = Context::default();
+  for x in 0..10 {
+    let values = [Number::new(x as f64, 0.0), Number::new(1.0, 0.0)];
+    let result = expr.evaluate_with(&ctx, &values).unwrap();
+    assert_eq!(result.re, (x * x + 1) as f64);
+  }
+  // Missing values fall back to the context
+  assert!(matches!(
+      expr.evaluate_with(&ctx, &[Number::new(1.0, 0.0)]),
+      Err(MathError::UndefinedVariable(name, _)) if name == "y"
+  ));
+}
+
+#[test]
+fn evaluates_against_a_context() {
+  let mut ctx = Context::default();
+  evaluate_in("f(t) = 2t".to_string(), &mut ctx).unwrap();
+  evaluate_in("x = 4".to_string(), &mut ctx).unwrap();
+  let expr = Expression::new("f(x) + 1").unwrap();
+  assert_eq!(expr.evaluate(&ctx).unwrap().re, 9.0);
+  assert_eq!(
+    expr
+      .evaluate_with(&ctx, &[Number::new(1.0, 0.0)])
+      .unwrap()
+      .re,
+    3.0
+  );
+}
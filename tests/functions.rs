@@ -0,0 +1,86 @@
+use math::{
+  error::MathError, evaluate, functions::Arity, number::Number, span::Span,
+};
+
+fn eval(input: &str) -> Result<Number, MathError> {
+  evaluate(input.to_string())
+}
+
+fn real(input: &str) -> f64 {
+  eval(input).unwrap().re
+}
+
+#[test]
+fn multi_argument_functions() {
+  assert_eq!(real("log(2, 8)"), 3.0);
+  assert_eq!(real("hypot(3, 4)"), 5.0);
+  assert_eq!(real("atan2(1, 1)"), std::f64::consts::FRAC_PI_4);
+  assert_eq!(real("atan2(1, -1)"), 3.0 * std::f64::consts::FRAC_PI_4);
+  assert_eq!(real("max(1, 2 + 3, -4)"), 5.0);
+  assert_eq!(real("min(3, 1, 2)"), 1.0);
+  assert_eq!(real("max(7)"), 7.0);
+  assert_eq!(real("2 max(1, 2)"), 4.0);
+  assert_eq!(real("max(min(4, 9), hypot(6, 8))"), 10.0);
+}
+
+#[test]
+fn exp_is_built_in() {
+  assert_eq!(real("exp(1)"), std::f64::consts::E);
+  assert_eq!(real("exp(0) + exp 0"), 2.0);
+  assert_eq!(real("ln(exp(2))"), 2.0);
+  let real_backend = math::evaluate_as::<f64>("exp(1)".to_string());
+  assert_eq!(real_backend.unwrap(), std::f64::consts::E);
+}
+
+#[test]
+fn argument_counts_are_checked() {
+  assert!(matches!(
+    eval("log(8)"),
+    Err(MathError::ArgumentCount(name, Arity::Exact(2), 1, span))
+      if name == "log" && span == Span::new(0, 6)
+  ));
+  assert!(matches!(
+    eval("atan2(1, 2, 3)"),
+    Err(MathError::ArgumentCount(name, Arity::Exact(2), 3, _)) if name == "atan2"
+  ));
+  assert!(matches!(
+    eval("sin(1, 2)"),
+    Err(MathError::ArgumentCount(_, Arity::Exact(1), 2, _))
+  ));
+  assert!(matches!(
+    eval("max()"),
+    Err(MathError::ArgumentCount(_, Arity::AtLeast(1), 0, _))
+  ));
+  assert_eq!(
+    eval("hypot(1)").unwrap_err().to_string(),
+    "The function hypot() expects 2 arguments, got 1"
+  );
+}
+
+#[test]
+fn separators_only_go_between_arguments() {
+  for input in ["1, 2", "(1, 2)"] {
+    assert!(
+      matches!(eval(input), Err(MathError::MisplacedSeparator(_))),
+      "{input}"
+    );
+  }
+  for input in ["max(1,, 2)", "max(1, 2,)"] {
+    assert!(
+      matches!(eval(input), Err(MathError::MissingOperand(_))),
+      "{input}"
+    );
+  }
+}
+
+#[test]
+fn extremes_of_complex_numbers_are_undefined() {
+  assert!(matches!(
+    eval("max(1, i)"),
+    Err(MathError::UndefinedOperation(..))
+  ));
+  assert!(matches!(
+    eval("hypot(i, 1)"),
+    Err(MathError::UndefinedOperation(..))
+  ));
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::lexemes;
+use math::{
+  context::Context, error::MathError, evaluate, evaluate_in, lexer::Lexeme,
+  number::Number,
+};
+
+#[test]
+fn suffixes_lex_into_the_literal() {
+  assert_eq!(lexemes("3i"), [Lexeme::Number("3i".to_string())]);
+  assert_eq!(lexemes("2.5j"), [Lexeme::Number("2.5j".to_string())]);
+  // Only when the suffix ends the word
+  assert_eq!(
+    lexemes("3in"),
+    [
+      Lexeme::Number("3".to_string()),
+      Lexeme::Identifier("in".to_string())
+    ]
+  );
+}
+
+#[test]
+fn suffixes_evaluate_to_imaginary_numbers() {
+  let eval = |input: &str| evaluate(input.to_string()).unwrap();
+  assert_eq!(eval("3i"), Number::new(0.0, 3.0));
+  assert_eq!(eval("1 + 2.5j"), Number::new(1.0, 2.5));
+  assert_eq!(eval("2i * 2i"), Number::new(-4.0, 0.0));
+  assert_eq!(eval("1e2i"), Number::new(0.0, 100.0));
+}
+
+#[test]
+fn suffixes_ignore_a_reassigned_i() {
+  let mut ctx = Context::default();
+  evaluate_in("i = 7".to_string(), &mut ctx).unwrap();
+  evaluate_in("j = 5".to_string(), &mut ctx).unwrap();
+  let mut eval =
+    |input: &str| evaluate_in(input.to_string(), &mut ctx).unwrap().unwrap();
+  assert_eq!(eval("3i"), Number::new(0.0, 3.0));
+  assert_eq!(eval("2.5j"), Number::new(0.0, 2.5));
+  // The variable itself still holds what was assigned
+  assert_eq!(eval("3 i"), Number::new(21.0, 0.0));
+}
+
+#[test]
+fn malformed_suffixes_are_bad_numbers() {
+  assert!(matches!(
+    evaluate("1.2.3i".to_string()),
+    Err(MathError::BadNumber(_))
+  ));
+}
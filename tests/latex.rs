@@ -0,0 +1,133 @@
+mod common;
+
+use common::{parse, parse_latex};
+use math::{context::Context, number::Number};
+
+fn latex_in(input: &str, ctx: &Context) -> String {
+  parse(input).to_latex(ctx)
+}
+
+fn latex(input: &str) -> String {
+  latex_in(input, &Context::default())
+}
+
+#[test]
+fn operators() {
+  assert_eq!(latex("1/2 + x"), "\\frac{1}{2} + x");
+  assert_eq!(latex("(a + b) / c"), "\\frac{a + b}{c}");
+  assert_eq!(latex("2x^(n + 1)"), "2 \\cdot x^{n + 1}");
+  assert_eq!(latex("(1/2)^2"), "\\left(\\frac{1}{2}\\right)^{2}");
+  assert_eq!(latex("(-2)^2"), "\\left(-2\\right)^{2}");
+  assert_eq!(latex("√(x + 1)"), "\\sqrt{x + 1}");
+  assert_eq!(
+    latex("(a - b) * -c"),
+    "\\left(a - b\\right) \\cdot \\left(-c\\right)"
+  );
+  assert_eq!(latex("(n + 1)! + 5%"), "\\left(n + 1\\right)! + 5\\%");
+  assert_eq!(latex("200 + (5%)"), "200 + \\left(5\\%\\right)");
+}
+
+#[test]
+fn functions_and_symbols() {
+  assert_eq!(
+    latex("sin(pi theta)"),
+    "\\sin\\left(\\pi \\cdot \\theta\\right)"
+  );
+  assert_eq!(latex("sqrt(2) + |x|"), "\\sqrt{2} + \\left|x\\right|");
+  assert_eq!(latex("log(2, 8)"), "\\log_{2}\\left(8\\right)");
+  assert_eq!(
+    latex("rate * f(x, y)"),
+    "\\mathrm{rate} \\cdot f\\left(x, y\\right)"
+  );
+  assert_eq!(latex("foo(1)"), "\\operatorname{foo}\\left(1\\right)");
+}
+
+#[test]
+fn complex_numbers() {
+  assert_eq!(latex("3i"), "3i");
+  assert_eq!(latex("1i"), "i");
+  assert_eq!(latex("(2i)^2"), "\\left(2i\\right)^{2}");
+}
+
+#[test]
+fn registered_names() {
+  let mut ctx = Context::default();
+  ctx.assign_latex_name("erf".to_string(), "\\mathrm{erf}".to_string());
+  assert_eq!(latex_in("erf(x)", &ctx), "\\mathrm{erf}\\left(x\\right)");
+}
+
+#[test]
+fn latex_input_matches_plain_input() {
+  let cases = [
+    ("\\frac{1}{2} + x", "1/2 + x"),
+    ("\\frac12", "1/2"),
+    ("\\frac{a + b}{c}^{2}", "((a + b)/c)^2"),
+    ("2 \\cdot 3 \\times 4", "2 * 3 * 4"),
+    ("x^{n+1}", "x^(n + 1)"),
+    ("x^23", "x^2 * 3"),
+    ("2xy", "2 * x * y"),
+    ("\\sqrt{x + 1}", "sqrt(x + 1)"),
+    ("\\sqrt[3]{8}", "8^(1/3)"),
+    ("\\left(1 + x\\right) \\cdot 2", "(1 + x) * 2"),
+    ("\\left|x - 1\\right|", "|x - 1|"),
+    ("\\sin\\left(\\pi \\theta\\right)", "sin(pi theta)"),
+    ("\\sin x", "sin x"),
+    ("\\operatorname{erf}(x)", "erf(x)"),
+    ("\\Gamma(5) + x", "gamma(5) + x"),
+    ("5\\% + 3 \\, x", "5% + 3x"),
+    ("\\log_{2}(8)", "log(2, 8)"),
+    ("\\log_2\\left(x + 1\\right)", "log(2, x + 1)"),
+    ("\\log_{10} x + 1", "log(10, x) + 1"),
+  ];
+  for (latex, plain) in cases {
+    assert_eq!(parse_latex(latex), parse(plain), "{latex}");
+  }
+  // Plain input has no subscripts, so these only come from LaTeX
+  assert_eq!(parse_latex("x_{1} + x_n").to_string(), "x_1 + x_n");
+}
+
+#[test]
+fn latex_input_evaluates() {
+  let eval = |input: &str| math::evaluate_latex(input.to_string()).unwrap().re;
+  assert_eq!(eval("\\frac{1}{4} + \\frac{3}{4}"), 1.0);
+  assert_eq!(eval("\\sqrt[3]{27}"), 3.0);
+  assert_eq!(eval("2^{10}"), 1024.0);
+  assert!(math::evaluate_latex("\\infty".to_string()).is_err());
+  assert!(math::evaluate_latex("\\frac{1}{2".to_string()).is_err());
+}
+
+#[test]
+fn greek_letters_named_like_functions_are_variables() {
+  assert_eq!(parse_latex("\\gamma").to_string(), "gamma");
+  assert_eq!(parse_latex("2\\gamma x").to_string(), "2 * gamma * x");
+  assert_eq!(
+    parse_latex("\\Gamma(5) \\gamma").to_string(),
+    "gamma(5) * gamma"
+  );
+  let mut ctx = Context::default();
+  ctx.assign_variable("gamma".to_string(), Number::new(0.5, 0.0));
+  let value = parse_latex("2\\gamma").evaluate(&ctx).unwrap();
+  assert_eq!(value, Number::new(1.0, 0.0));
+}
+
+#[test]
+fn exported_latex_evaluates_like_the_input() {
+  let ctx = Context::default();
+  for input in [
+    "1/2 + 3^2",
+    "log(2, 8) + sqrt(16)",
+    "log(10, 1000 + 9000)",
+    "|-3| + 4! - (-2)^2",
+    "200 + (10%) - 5%",
+    "sin(pi / 2) cos(0)",
+    "gamma(5) - max(1, 2) min(3, 4)",
+    "2^(1/2) e",
+  ] {
+    let latex = parse(input).to_latex(&ctx);
+    assert_eq!(
+      math::evaluate_latex(latex.clone()).unwrap(),
+      math::evaluate(input.to_string()).unwrap(),
+      "{input} as {latex}"
+    );
+  }
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::lexemes;
+use math::{
+  error::MathError, evaluate, evaluate_to_string_in, lexer::Lexeme,
+  number::Base, span::Span,
+};
+
+fn number(s: &str) -> Lexeme {
+  Lexeme::Number(s.to_string())
+}
+
+fn eval(input: &str) -> f64 {
+  evaluate(input.to_string()).unwrap().re
+}
+
+#[test]
+fn exponents_lex_into_the_literal() {
+  assert_eq!(lexemes("1E-9"), [number("1E-9")]);
+  assert_eq!(lexemes("2.5e3"), [number("2.5e3")]);
+  assert_eq!(lexemes("1e+2"), [number("1e+2")]);
+}
+
+#[test]
+fn markers_without_digits_are_the_constant_e() {
+  let e = Lexeme::Identifier("e".to_string());
+  assert_eq!(lexemes("2e"), [number("2"), e.clone()]);
+  assert_eq!(
+    lexemes("2e+x"),
+    [
+      number("2"),
+      e,
+      Lexeme::Special("+".to_string()),
+      Lexeme::Identifier("x".to_string())
+    ]
+  );
+}
+
+#[test]
+fn exponents_evaluate() {
+  assert_eq!(eval("1E-9"), 1e-9);
+  assert_eq!(eval("2.5e3"), 2500.0);
+  assert_eq!(eval("1e+2"), 100.0);
+  assert_eq!(eval("2e"), 2.0 * std::f64::consts::E);
+  assert_eq!(eval("3e-5 * 2"), 6e-5);
+}
+
+#[test]
+fn float_noise_still_snaps_to_zero() {
+  assert_eq!(eval("sin(100pi)"), 0.0);
+  assert_eq!(eval("sin(1000 pi)"), 0.0);
+  assert_eq!(eval("2.99999999"), 3.0);
+  assert_eq!(eval("1e-9 + 1"), 1.0);
+  assert_eq!(eval("-1E-9"), -1e-9);
+}
+
+#[test]
+fn radix_literals_evaluate() {
+  assert_eq!(eval("0xFF"), 255.0);
+  assert_eq!(eval("0b1010 + 0o755"), 503.0);
+  assert_eq!(eval("0XfF_fF"), 65535.0);
+  assert_eq!(eval("0xFFFFFFFFFFFFFFFFFF"), 2f64.powi(72));
+  assert_eq!(eval("1_000_000 + 2.5_0"), 1000002.5);
+  // Without digits after the prefix it's still zero times `x`
+  assert_eq!(lexemes("0x")[0], number("0"));
+}
+
+#[test]
+fn bad_digits_are_rejected_as_a_whole() {
+  for input in ["0b102", "0o78", "0xFG"] {
+    assert_eq!(lexemes(input), [number(input)], "{input}");
+    let span = Span::new(0, input.len());
+    assert!(
+      matches!(
+        evaluate(input.to_string()),
+        Err(MathError::BadNumber(s)) if s == span
+      ),
+      "{input}"
+    );
+  }
+}
+
+#[test]
+fn results_render_in_a_chosen_base() {
+  let shown =
+    |input: &str, base| evaluate_to_string_in(input.to_string(), base);
+  assert_eq!(shown("0xFF + 1", Base::Hexadecimal), "0x100");
+  assert_eq!(shown("10", Base::Binary), "0b1010");
+  assert_eq!(shown("-8", Base::Octal), "-0o10");
+  assert_eq!(shown("0xFF", Base::Decimal), "255");
+  // Only integers have a prefixed form
+  assert_eq!(shown("1/2", Base::Hexadecimal), "0.5");
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::parse;
+use math::{
+  context::Context,
+  functions::{Arity, Function},
+};
+
+/// Without the surrounding `<math>` element
+fn inner(mathml: String) -> String {
+  let start = mathml.find('>').unwrap() + 1;
+  mathml[start..mathml.len() - "</math>".len()].to_string()
+}
+
+fn presentation(input: &str) -> String {
+  inner(parse(input).to_presentation_mathml(&Context::default()))
+}
+
+fn content(input: &str) -> String {
+  inner(parse(input).to_content_mathml(&Context::default()))
+}
+
+#[test]
+fn presentation_operators() {
+  assert_eq!(
+    parse("x").to_presentation_mathml(&Context::default()),
+    "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mi>x</mi></math>"
+  );
+  assert_eq!(presentation("1/2"), "<mfrac><mn>1</mn><mn>2</mn></mfrac>");
+  assert_eq!(
+        presentation("(x + 1)^2"),
+        "<msup><mrow><mo>(</mo><mrow><mi>x</mi><mo>+</mo><mn>1</mn></mrow><mo>)</mo></mrow><mn>2</mn></msup>"
+    );
+  assert_eq!(presentation("√x"), "<msqrt><mi>x</mi></msqrt>");
+  assert_eq!(
+        presentation("-2 * pi"),
+        "<mrow><mrow><mo>\u{2212}</mo><mn>2</mn></mrow><mo>\u{22C5}</mo><mi>\u{3C0}</mi></mrow>"
+    );
+  assert_eq!(presentation("3!"), "<mrow><mn>3</mn><mo>!</mo></mrow>");
+  assert_eq!(presentation("5%"), "<mrow><mn>5</mn><mo>%</mo></mrow>");
+  assert_eq!(
+    presentation("2i"),
+    "<mrow><mn>2</mn><mo>&#x2062;</mo><mi>i</mi></mrow>"
+  );
+}
+
+#[test]
+fn presentation_functions() {
+  assert_eq!(
+        presentation("sin(theta)"),
+        "<mrow><mi>sin</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mi>\u{3B8}</mi><mo>)</mo></mrow></mrow>"
+    );
+  assert_eq!(
+    presentation("|x|"),
+    "<mrow><mo>|</mo><mi>x</mi><mo>|</mo></mrow>"
+  );
+  assert_eq!(
+        presentation("log(2, x)"),
+        "<mrow><msub><mi>log</mi><mn>2</mn></msub><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mrow>"
+    );
+  assert_eq!(presentation("omega"), "<mi>\u{3C9}</mi>");
+}
+
+#[test]
+fn content_operators() {
+  assert_eq!(
+        content("2 * x - 1"),
+        "<apply><minus/><apply><times/><cn>2</cn><ci>x</ci></apply><cn>1</cn></apply>"
+    );
+  assert_eq!(
+        content("e^(i pi)"),
+        "<apply><power/><exponentiale/><apply><times/><imaginaryi/><pi/></apply></apply>"
+    );
+  assert_eq!(
+    content("√-x"),
+    "<apply><root/><apply><minus/><ci>x</ci></apply></apply>"
+  );
+  assert_eq!(content("4!"), "<apply><factorial/><cn>4</cn></apply>");
+  assert_eq!(
+        content("200 + 10%"),
+        "<apply><plus/><cn>200</cn><apply><times/><cn>200</cn><apply><divide/><cn>10</cn><cn>100</cn></apply></apply></apply>"
+    );
+  assert_eq!(
+    content("3i"),
+    "<cn type=\"complex-cartesian\">0<sep/>3</cn>"
+  );
+}
+
+#[test]
+fn every_builtin_function_renders() {
+  for (name, function) in Function::default_set() {
+    let count = match function.arity() {
+      Arity::Exact(n) | Arity::AtLeast(n) => n,
+    };
+    let args = vec!["x"; count].join(", ");
+    let ast = parse(&format!("{name}({args})"));
+    let ctx = Context::default();
+    assert!(!ast.to_presentation_mathml(&ctx).is_empty());
+    assert!(ast.to_content_mathml(&ctx).contains("<apply>"), "{name}");
+  }
+  assert_eq!(content("arcsin(x)"), "<apply><arcsin/><ci>x</ci></apply>");
+  assert_eq!(
+    content("log(2, x)"),
+    "<apply><log/><logbase><cn>2</cn></logbase><ci>x</ci></apply>"
+  );
+  assert_eq!(
+    content("gamma(x)"),
+    "<apply><ci type=\"function\">gamma</ci><ci>x</ci></apply>"
+  );
+}
+
+#[test]
+fn custom_function_hook() {
+  let mut ctx = Context::default();
+  ctx.assign_mathml_name(
+    "erf".to_string(),
+    "<mi>erf</mi>".to_string(),
+    "<csymbol cd=\"custom\">erf</csymbol>".to_string(),
+  );
+  let ast = parse("erf(x)");
+  assert_eq!(
+    inner(ast.to_content_mathml(&ctx)),
+    "<apply><csymbol cd=\"custom\">erf</csymbol><ci>x</ci></apply>"
+  );
+  assert!(ast.to_presentation_mathml(&ctx).contains("<mi>erf</mi>"));
+}
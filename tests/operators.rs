@@ -0,0 +1,52 @@
+use math::{
+  error::MathError,
+  evaluate,
+  lexer::{self, Lexeme},
+  operator::{Operation, OperatorSet},
+};
+
+fn specials(input: &str, op_set: &OperatorSet) -> Vec<String> {
+  lexer::lex(input.to_string(), op_set)
+    .into_iter()
+    .filter_map(|l| match l.item {
+      Lexeme::Special(s) => Some(s),
+      _ => None,
+    })
+    .collect()
+}
+
+#[test]
+fn longest_registered_symbol_wins() {
+  let op_set = OperatorSet::default();
+  assert_eq!(specials("2**3", &op_set), ["**"]);
+  assert_eq!(specials("2*-3", &op_set), ["*", "-"]);
+  assert_eq!(specials("2***3", &op_set), ["**", "*"]);
+  assert_eq!(specials("2* *3", &op_set), ["*", "*"]);
+}
+
+#[test]
+fn double_star_is_exponentiation() {
+  assert_eq!(evaluate("2**3".to_string()).unwrap().re, 8.0);
+  assert_eq!(evaluate("2**3**2".to_string()).unwrap().re, 512.0);
+  assert_eq!(evaluate("-2**2".to_string()).unwrap().re, -4.0);
+  assert!(matches!(
+    evaluate("2***3".to_string()),
+    Err(MathError::MissingOperand(_))
+  ));
+}
+
+#[test]
+fn custom_sets_register_any_symbol() {
+  let op_set: OperatorSet = OperatorSet::new(
+    vec![
+      ("/", Operation::division()),
+      ("//", Operation::exponentiation()),
+      ("<=", Operation::subtraction()),
+    ],
+    Operation::multiplication(),
+  );
+  assert_eq!(specials("a//b/c<=d", &op_set), ["//", "/", "<="]);
+  // Symbols the set doesn't know aren't operators
+  let lexemes = lexer::lex("2+3".to_string(), &op_set);
+  assert_eq!(lexemes[1].item, Lexeme::Unknown('+'));
+}
@@ -0,0 +1,50 @@
+use math::{error::MathError, evaluate, number::Number};
+
+fn eval(input: &str) -> Result<Number, MathError> {
+  evaluate(input.to_string())
+}
+
+fn close(input: &str, expected: Number) {
+  let result = eval(input).unwrap();
+  assert!((result - expected).norm() < 1e-12, "{input} = {result}");
+}
+
+#[test]
+fn factorials() {
+  assert_eq!(eval("5!").unwrap().re, 120.0);
+  assert_eq!(eval("0!").unwrap().re, 1.0);
+  assert_eq!(eval("3!!").unwrap().re, 720.0);
+  // Postfix binds tighter than prefix and infix operators
+  assert_eq!(eval("-3!").unwrap().re, -6.0);
+  assert_eq!(eval("2^3!").unwrap().re, 64.0);
+  assert_eq!(eval("2 3!").unwrap().re, 12.0);
+  assert!(matches!(eval("171!"), Err(MathError::TooBig(_))));
+  assert!(matches!(eval("(-1)!"), Err(MathError::Undefined(_))));
+}
+
+#[test]
+fn factorials_extend_through_gamma() {
+  let root_pi = std::f64::consts::PI.sqrt();
+  close("0.5!", Number::new(root_pi / 2.0, 0.0));
+  close("(-0.5)!", Number::new(root_pi, 0.0));
+  close("gamma(0.5)", Number::new(root_pi, 0.0));
+  close("gamma(5)", Number::new(24.0, 0.0));
+  close("i!", Number::new(0.498015668118356, -0.1549498283018106));
+}
+
+#[test]
+fn percent_is_relative_to_the_left_operand() {
+  assert_eq!(eval("200 + 10%").unwrap().re, 220.0);
+  assert_eq!(eval("200 - 10%").unwrap().re, 180.0);
+  assert_eq!(eval("200 + 10% + 10%").unwrap().re, 242.0);
+  // Other operators use the plain fraction
+  assert_eq!(eval("200 * 10%").unwrap().re, 20.0);
+  assert_eq!(eval("50%").unwrap().re, 0.5);
+  assert_eq!(eval("(200 + 10)%").unwrap().re, 2.1);
+}
+
+#[test]
+fn postfix_operators_need_an_operand() {
+  assert!(matches!(eval("!"), Err(MathError::MissingOperand(_))));
+  assert!(matches!(eval("% 5"), Err(MathError::MissingOperand(_))));
+}
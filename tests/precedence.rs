@@ -0,0 +1,79 @@
+mod common;
+
+use math::{error::MathError, evaluate, functions::Function};
+
+fn eval(input: &str) -> Result<f64, MathError> {
+  evaluate(input.to_string()).map(|n| n.re)
+}
+
+/// Parses and prints back, to show how the input was grouped
+fn parsed(input: &str) -> String {
+  common::parse(input).to_string()
+}
+
+#[test]
+fn prefix_and_infix_operators() {
+  assert_eq!(eval("-2^2").unwrap(), -4.0);
+  assert_eq!(eval("(-2)^2").unwrap(), 4.0);
+  assert_eq!(eval("2^-3").unwrap(), 0.125);
+  assert_eq!(eval("2^3^2").unwrap(), 512.0);
+  assert_eq!(eval("2*-3").unwrap(), -6.0);
+  assert_eq!(eval("1 - 2 - 3").unwrap(), -4.0);
+  assert_eq!(eval("12 / 2 / 3").unwrap(), 2.0);
+  assert_eq!(eval("2 + 3 * 4^2").unwrap(), 50.0);
+  assert_eq!(eval("2(3 + 4)").unwrap(), 14.0);
+}
+
+#[test]
+fn bare_calls_take_their_argument_through_implicit_products() {
+  assert_eq!(parsed("sin x^2"), "sin(x ^ 2)");
+  assert_eq!(parsed("sin 2x"), "sin(2 * x)");
+  assert_eq!(parsed("sin -x"), "sin(-x)");
+  assert_eq!(parsed("sin x + 1"), "sin(x) + 1");
+  assert_eq!(parsed("sin x * 2"), "sin(x) * 2");
+  assert_eq!(parsed("sin x cos x"), "sin(x) * cos(x)");
+  assert_eq!(parsed("2 sin x"), "2 * sin(x)");
+  assert_eq!(eval("sqrt 4 9").unwrap(), 6.0);
+  assert_eq!(eval("ln e^2").unwrap(), 2.0);
+}
+
+#[test]
+fn builtin_names_without_an_argument_are_variables() {
+  assert_eq!(parsed("gamma - 1"), "gamma - 1");
+  assert_eq!(parsed("gamma -1"), "gamma(-1)");
+  assert_eq!(parsed("2 gamma"), "2 * gamma");
+  assert_eq!(parsed("min / 2"), "min / 2");
+  assert_eq!(parsed("|sin| x"), "|sin| * x");
+  assert!(matches!(
+    eval("sin"),
+    Err(MathError::UndefinedVariable(name, _)) if name == "sin"
+  ));
+}
+
+#[test]
+fn malformed_input_gets_specific_errors() {
+  assert!(matches!(eval("2 +"), Err(MathError::MissingOperand(_))));
+  assert!(matches!(eval("sin +"), Err(MathError::MissingOperand(_))));
+  assert!(matches!(
+    eval("(1 + 2"),
+    Err(MathError::UnmatchedWrapping(_))
+  ));
+  assert!(matches!(
+    eval("1 + 2)"),
+    Err(MathError::UnmatchedWrapping(_))
+  ));
+  assert!(matches!(
+    eval("(1 + 2]"),
+    Err(MathError::UnmatchedWrapping(_))
+  ));
+  assert!(matches!(eval(""), Err(MathError::NoInput(_))));
+}
+
+#[test]
+fn builtin_names_match_the_builtins() {
+  let builtins = Function::default_set();
+  for name in builtins.keys() {
+    assert!(Function::is_builtin(name), "{name}");
+  }
+  assert!(!Function::is_builtin("f"));
+}
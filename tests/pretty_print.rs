@@ -0,0 +1,52 @@
+mod common;
+
+use common::parse;
+
+/// Prints `input` and checks the output parses back into the same tree
+fn normalize(input: &str) -> String {
+  let ast = parse(input);
+  let printed = ast.to_string();
+  assert_eq!(parse(&printed), ast, "{input} printed as {printed}");
+  printed
+}
+
+#[test]
+fn drops_redundant_parentheses() {
+  assert_eq!(normalize("(1 + 2) + 3"), "1 + 2 + 3");
+  assert_eq!(normalize("1 + (2 * 3)"), "1 + 2 * 3");
+  assert_eq!(normalize("((x))"), "x");
+  assert_eq!(normalize("2^(3^2)"), "2 ^ 3 ^ 2");
+  assert_eq!(normalize("-(2^2)"), "-2 ^ 2");
+  assert_eq!(normalize("(-2) * 3"), "-2 * 3");
+}
+
+#[test]
+fn keeps_required_parentheses() {
+  assert_eq!(normalize("1 - (2 - 3)"), "1 - (2 - 3)");
+  assert_eq!(normalize("(1 + 2) * 3"), "(1 + 2) * 3");
+  assert_eq!(normalize("(2^3)^2"), "(2 ^ 3) ^ 2");
+  assert_eq!(normalize("(-2)^2"), "(-2) ^ 2");
+  assert_eq!(normalize("(1 + 2)!"), "(1 + 2)!");
+  assert_eq!(normalize("(-3)!"), "(-3)!");
+  assert_eq!(normalize("-(1 + x)"), "-(1 + x)");
+  assert_eq!(normalize("200 + (10%)"), "200 + (10%)");
+}
+
+#[test]
+fn grouping_a_percentage_changes_its_meaning() {
+  assert_ne!(parse("200 + (10%)"), parse("200 + 10%"));
+  assert_eq!(parse("200 + ((10%))"), parse("200 + (10%)"));
+  let value =
+    |input: &str| math::evaluate(parse(input).to_string()).unwrap().re;
+  assert!((value("200 + (10%)") - 200.1).abs() < 1e-9);
+  assert!((value("200 + 10%") - 220.0).abs() < 1e-9);
+}
+
+#[test]
+fn writes_implicit_operations_and_calls() {
+  assert_eq!(normalize("2x"), "2 * x");
+  assert_eq!(normalize("sin x + max(1, 2)"), "sin(x) + max(1, 2)");
+  assert_eq!(normalize("200 + 10%"), "200 + 10%");
+  assert_eq!(normalize("3i |x - 1|"), "3i * |x - 1|");
+  assert_eq!(normalize("√-4 / 2e3"), "√-4 / 2000");
+}
@@ -0,0 +1,64 @@
+use math::{
+  context::Context, error::MathError, evaluate, evaluate_in, evaluate_latex,
+  evaluate_program, expression::Expression, number::Number,
+};
+
+fn run(input: &str) -> Result<Vec<Option<f64>>, MathError> {
+  let mut ctx = Context::default();
+  evaluate_program(input.to_string(), &mut ctx)
+    .map(|results| results.into_iter().map(|n| n.map(|n| n.re)).collect())
+}
+
+#[test]
+fn statements_share_one_context() {
+  let results = run("r = 2; area = pi r^2; area * 3").unwrap();
+  let area = std::f64::consts::PI * 4.0;
+  assert_eq!(results, vec![Some(2.0), Some(area), Some(area * 3.0)]);
+}
+
+#[test]
+fn newlines_separate_statements() {
+  let results = run("f(x) = x^2\n\nf(3)\n").unwrap();
+  assert_eq!(results, vec![None, Some(9.0)]);
+  assert_eq!(run("1;;2;").unwrap(), vec![Some(1.0), Some(2.0)]);
+}
+
+#[test]
+fn newlines_inside_wrappings_continue_the_statement() {
+  let results = run("a = 1\nb = (a +\n 2)\nmax(a,\n b)").unwrap();
+  assert_eq!(results, vec![Some(1.0), Some(3.0), Some(3.0)]);
+  // Closing the wrapping lets newlines end statements again
+  assert_eq!(run("(1 +\n2)\n3").unwrap(), vec![Some(3.0), Some(3.0)]);
+}
+
+#[test]
+fn statements_cannot_span_wrappings() {
+  assert!(matches!(
+    run("(1; 2)"),
+    Err(MathError::UnmatchedWrapping(_))
+  ));
+  assert!(matches!(run(" ; \n"), Err(MathError::NoInput(_))));
+}
+
+#[test]
+fn single_statements_treat_newlines_as_whitespace() {
+  let value = |n: Number| n.re;
+  assert_eq!(evaluate("1+2\n".to_string()).map(value).unwrap(), 3.0);
+  assert_eq!(evaluate("\n2\n*\n3".to_string()).map(value).unwrap(), 6.0);
+  assert_eq!(
+    evaluate_latex("\\frac{1}{2}\n".to_string())
+      .map(value)
+      .unwrap(),
+    0.5
+  );
+  let expr = Expression::new("x^2\n").unwrap();
+  assert_eq!(expr.variables(), ["x"]);
+  let mut ctx = Context::default();
+  let result = evaluate_in("x = 3\n".to_string(), &mut ctx).unwrap();
+  assert_eq!(result.map(value), Some(3.0));
+  assert_eq!(
+    expr.evaluate(&ctx).map(value).unwrap(),
+    9.0,
+    "x was assigned"
+  );
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::run_program;
+use math::{
+  error::MathError, evaluate_as, number::result_to_string, rational::Rational,
+};
+
+fn exact(input: &str) -> Result<Rational, MathError> {
+  evaluate_as::<Rational>(input.to_string())
+}
+
+fn shown(input: &str) -> String {
+  result_to_string(exact(input))
+}
+
+#[test]
+fn arithmetic_stays_exact() {
+  assert_eq!(shown("1/3 + 1/6"), "1/2");
+  assert_eq!(shown("0.1 + 0.2"), "3/10");
+  assert_eq!(shown("1/3 * 3"), "1");
+  assert_eq!(shown("-(2/4)"), "-1/2");
+  assert_eq!(shown("200 + 10%"), "220");
+  assert_eq!(shown("max(1/3, 0.3)"), "1/3");
+  assert_eq!(shown("|1/3 - 1/2|"), "1/6");
+  assert!(exact("1/3 + 1/6").unwrap().is_exact());
+}
+
+#[test]
+fn literals_parse_exactly() {
+  assert_eq!(shown("1e-3"), "1/1000");
+  assert_eq!(shown("2.5E2"), "250");
+  assert_eq!(shown("0x1F + 0b11"), "34");
+  assert_eq!(shown("0xFFFFFFFFFFFFFFFFFF"), "4722366482869645213695");
+  assert!(matches!(exact("2i"), Err(MathError::BadNumber(_))));
+}
+
+#[test]
+fn powers_stay_exact() {
+  assert_eq!(shown("2^-3"), "1/8");
+  assert_eq!(shown("(2/3)^3"), "8/27");
+  assert_eq!(shown("2^100"), "1267650600228229401496703205376");
+  assert_eq!(shown("8^(2/3)"), "4");
+  assert_eq!(shown("sqrt(4/9)"), "2/3");
+  assert_eq!(shown("(-1)^1000001"), "-1");
+  assert_eq!(shown("25!"), "15511210043330985984000000");
+  assert!(matches!(exact("2^(10^10)"), Err(MathError::TooBig(_))));
+  assert!(matches!(exact("0^-1"), Err(MathError::DivisionByZero(_))));
+  assert!(matches!(
+    exact("1/(1/2 - 0.5)"),
+    Err(MathError::DivisionByZero(_))
+  ));
+}
+
+#[test]
+fn transcendental_functions_fall_back_to_floats() {
+  let sine = exact("sin(1/2)").unwrap();
+  assert!(!sine.is_exact());
+  assert!((sine.to_f64() - 0.5f64.sin()).abs() < 1e-15);
+  assert!(!exact("2 pi").unwrap().is_exact());
+  assert!(!exact("sqrt(2)").unwrap().is_exact());
+  // Once inexact, always inexact
+  assert!(!exact("sqrt(2)^2 - 1").unwrap().is_exact());
+  assert!(matches!(
+    exact("sqrt(-4)"),
+    Err(MathError::ComplexResult(_))
+  ));
+}
+
+#[test]
+fn programs_run_exactly() {
+  let results = run_program::<Rational>("a = 1/3; f(x) = x^2 + a; f(1/2)");
+  assert_eq!(results[2].as_ref().unwrap().to_string(), "7/12");
+}
@@ -0,0 +1,41 @@
+use math::{context::Context, error::MathError, evaluate_in};
+
+fn run(ctx: &mut Context, input: &str) -> Result<Option<f64>, MathError> {
+  evaluate_in(input.to_string(), ctx).map(|n| n.map(|n| n.re))
+}
+
+#[test]
+fn function_bodies_see_globals_and_builtins() {
+  let mut ctx = Context::default();
+  run(&mut ctx, "f(x) = sin(x) + pi").unwrap();
+  assert_eq!(run(&mut ctx, "f(0)").unwrap(), Some(std::f64::consts::PI));
+  run(&mut ctx, "a = 10").unwrap();
+  run(&mut ctx, "g(x) = f(0) + a + x").unwrap();
+  assert_eq!(
+    run(&mut ctx, "g(1)").unwrap(),
+    Some(std::f64::consts::PI + 11.0)
+  );
+}
+
+#[test]
+fn parameters_shadow_outer_names() {
+  let mut ctx = Context::default();
+  run(&mut ctx, "x = 100").unwrap();
+  run(&mut ctx, "f(x) = x * 2").unwrap();
+  assert_eq!(run(&mut ctx, "f(3)").unwrap(), Some(6.0));
+  assert_eq!(run(&mut ctx, "x").unwrap(), Some(100.0));
+  // Callers' parameters don't leak into the functions they call
+  run(&mut ctx, "k(z) = z + y").unwrap();
+  run(&mut ctx, "g(y) = k(1)").unwrap();
+  assert!(matches!(
+    run(&mut ctx, "g(7)"),
+    Err(MathError::UndefinedVariable(name, _)) if name == "y"
+  ));
+}
+
+#[test]
+fn runaway_recursion_is_an_error() {
+  let mut ctx = Context::default();
+  run(&mut ctx, "r(x) = r(x)").unwrap();
+  assert!(matches!(run(&mut ctx, "r(1)"), Err(MathError::TooDeep(_))));
+}
@@ -0,0 +1,58 @@
+use math::{
+  error::MathError,
+  evaluate,
+  lexer::{self, Lexeme},
+  operator::OperatorSet,
+  span::Span,
+};
+
+fn error(input: &str) -> MathError {
+  evaluate(input.to_string()).unwrap_err()
+}
+
+#[test]
+fn lexemes_carry_byte_spans() {
+  let op_set = OperatorSet::default();
+  let lexemes = lexer::lex("12 + ab".to_string(), &op_set);
+  let spans: Vec<_> = lexemes.iter().map(|l| l.span).collect();
+  assert_eq!(spans, [Span::new(0, 2), Span::new(3, 4), Span::new(5, 7)]);
+  assert_eq!(lexemes[2].item, Lexeme::Identifier("ab".to_string()));
+}
+
+#[test]
+fn errors_point_at_the_problem() {
+  assert_eq!(error("1 + $").span(), Span::new(4, 5));
+  assert_eq!(error("(1 + 2").span(), Span::new(0, 1));
+  assert_eq!(error("2 * nope").span(), Span::new(4, 8));
+  assert_eq!(error("1.2.3 + 1").span(), Span::new(0, 5));
+}
+
+#[test]
+fn spans_count_chars_for_non_ascii_input() {
+  let input = "π + $";
+  let span = error(input).span();
+  assert_eq!(span, Span::new(5, 6));
+  assert_eq!(span.chars(input), 4..5);
+}
+
+#[test]
+fn render_underlines_the_span() {
+  let input = "2 * nope";
+  assert_eq!(
+    error(input).render(input),
+    "The variable nope is undefined\n2 * nope\n    ^^^^"
+  );
+  let input = "π + $";
+  assert_eq!(
+    error(input).render(input),
+    "Unrecognized symbol\nπ + $\n    ^"
+  );
+}
+
+#[test]
+fn underline_shows_only_the_line_with_the_span() {
+  let input = "a = 1\nb = $\nc = 3";
+  assert_eq!(Span::new(10, 11).underline(input), "b = $\n    ^");
+  // Empty spans still get a caret
+  assert_eq!(Span::at(2).underline("1 +"), "1 +\n  ^");
+}
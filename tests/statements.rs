@@ -0,0 +1,88 @@
+mod common;
+
+use common::tokens;
+use math::{
+  context::Context, error::MathError, evaluate_in, number::Number,
+  parse::Statement, span::Span,
+};
+
+fn statement(input: &str) -> Result<Statement, MathError> {
+  Statement::new(tokens(input))
+}
+
+#[test]
+fn assignments_and_definitions_parse() {
+  match statement("x = 3 + 4").unwrap() {
+    Statement::Assignment { name, value, span } => {
+      assert_eq!(name, "x");
+      assert_eq!(value.to_string(), "3 + 4");
+      assert_eq!(span, Span::new(0, 1));
+    },
+    other => panic!("{other}"),
+  }
+  match statement("f(x, y) = x^2 + y").unwrap() {
+    Statement::FunctionDefinition {
+      name, params, body, ..
+    } => {
+      assert_eq!(name, "f");
+      assert_eq!(params, ["x", "y"]);
+      assert_eq!(body.to_string(), "x ^ 2 + y");
+    },
+    other => panic!("{other}"),
+  }
+  assert!(matches!(
+    statement("2x + 1").unwrap(),
+    Statement::Expression(_)
+  ));
+}
+
+#[test]
+fn statements_print_back() {
+  for input in ["x = 3 + 4", "f(x, y) = x ^ 2 + y", "g() = 1", "2 * x"] {
+    assert_eq!(statement(input).unwrap().to_string(), input);
+  }
+}
+
+#[test]
+fn only_names_can_be_assigned_to() {
+  for input in ["2 = 3", "x + 1 = 3", "f(2) = 3", "f(x,) = 3", "f(x y) = 3"] {
+    assert!(
+      matches!(statement(input), Err(MathError::InvalidAssignment(_))),
+      "{input}"
+    );
+  }
+  assert!(matches!(
+    statement("= 3"),
+    Err(MathError::InvalidAssignment(span)) if span == Span::new(0, 1)
+  ));
+  assert!(matches!(
+    statement("x ="),
+    Err(MathError::MissingOperand(span)) if span == Span::at(3)
+  ));
+}
+
+#[test]
+fn statements_update_the_context() {
+  let mut ctx = Context::default();
+  let mut run = |input: &str| evaluate_in(input.to_string(), &mut ctx);
+  assert_eq!(run("x = 3").unwrap(), Some(Number::new(3.0, 0.0)));
+  assert_eq!(run("f(t) = t x").unwrap(), None);
+  assert_eq!(run("f(2)").unwrap(), Some(Number::new(6.0, 0.0)));
+  assert_eq!(run("x = x + 1").unwrap(), Some(Number::new(4.0, 0.0)));
+  assert!(matches!(run("f(1, 2)"), Err(MathError::ArgumentCount(..))));
+}
+
+#[test]
+fn builtin_function_names_can_be_assigned() {
+  let mut ctx = Context::default();
+  let mut run = |input: &str| evaluate_in(input.to_string(), &mut ctx);
+  assert_eq!(run("gamma = 0.5").unwrap(), Some(Number::new(0.5, 0.0)));
+  assert_eq!(run("min = 3").unwrap(), Some(Number::new(3.0, 0.0)));
+  assert_eq!(run("gamma").unwrap(), Some(Number::new(0.5, 0.0)));
+  assert_eq!(run("2 gamma + min").unwrap(), Some(Number::new(4.0, 0.0)));
+  // Calls still reach the function
+  assert_eq!(
+    run("gamma(4) + min(2, 1)").unwrap(),
+    Some(Number::new(7.0, 0.0))
+  );
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::lexemes;
+use math::{
+  context::Context, error::MathError, evaluate, evaluate_in, lexer::Lexeme,
+  number::Number,
+};
+
+fn eval(input: &str) -> Number {
+  evaluate(input.to_string()).unwrap()
+}
+
+#[test]
+fn operators_map_to_plain_ones() {
+  assert_eq!(eval("6 × 7").re, 42.0);
+  assert_eq!(eval("3 · 4 ⋅ 2").re, 24.0);
+  assert_eq!(eval("1 ÷ 4").re, 0.25);
+  assert_eq!(eval("5 − 8").re, -3.0);
+  assert_eq!(eval("−2").re, -2.0);
+}
+
+#[test]
+fn square_roots_and_constants() {
+  assert_eq!(eval("√16").re, 4.0);
+  assert_eq!(eval("√(9) + 1").re, 4.0);
+  assert_eq!(eval("2√4").re, 4.0);
+  assert_eq!(eval("π").re, std::f64::consts::PI);
+  assert_eq!(eval("τ / 2").re, std::f64::consts::PI);
+  assert_eq!(eval("ℯ").re, std::f64::consts::E);
+  assert_eq!(eval("2π").re, std::f64::consts::TAU);
+}
+
+#[test]
+fn superscripts_are_exponents() {
+  assert_eq!(eval("3²").re, 9.0);
+  assert_eq!(eval("2³ + 1").re, 9.0);
+  assert_eq!(eval("10¹²").re, 1e12);
+  assert_eq!(eval("2⁻¹").re, 0.5);
+  assert_eq!(eval("−2²").re, -4.0);
+  assert_eq!(
+    lexemes("x²"),
+    [
+      Lexeme::Identifier("x".to_string()),
+      Lexeme::Special("^".to_string()),
+      Lexeme::Number("2".to_string())
+    ]
+  );
+}
+
+#[test]
+fn imaginary_unit_ignores_the_variable_i() {
+  assert_eq!(eval("ⅈ²"), Number::new(-1.0, 0.0));
+  assert_eq!(eval("3 + 2ⅉ"), Number::new(3.0, 2.0));
+  let mut ctx = Context::default();
+  evaluate_in("i = 7".to_string(), &mut ctx).unwrap();
+  let result = evaluate_in("2ⅈ".to_string(), &mut ctx).unwrap();
+  assert_eq!(result, Some(Number::new(0.0, 2.0)));
+}
+
+#[test]
+fn unknown_symbols_are_still_rejected() {
+  assert!(matches!(
+    evaluate("2 ∞ 3".to_string()),
+    Err(MathError::IllegalSymbol(_))
+  ));
+}
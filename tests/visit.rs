@@ -0,0 +1,110 @@
+mod common;
+
+use math::{
+  context::Context,
+  operator::Operation,
+  parse::AstNode,
+  span::Span,
+  visit::{Fold, VisitorMut, Walker},
+};
+
+fn parse(input: &str) -> AstNode {
+  common::parse(input).root
+}
+
+/// Counts the variables an expression reads
+struct VariableCount;
+
+impl Walker for VariableCount {
+  type Output = usize;
+
+  fn merge(&mut self, first: usize, later: usize) -> usize {
+    first + later
+  }
+
+  fn visit_variable(&mut self, _name: &str, _span: Span) -> usize {
+    1
+  }
+}
+
+#[test]
+fn walker_only_overrides_what_it_needs() {
+  assert_eq!(
+    parse("x + max(y, |x|) * -(z + 1)!").walk(&mut VariableCount),
+    4
+  );
+  assert_eq!(parse("2 + 3").walk(&mut VariableCount), 0);
+}
+
+struct Rename;
+
+impl VisitorMut for Rename {
+  fn visit_variable_mut(&mut self, name: &mut String, _span: Span) {
+    if name == "x" {
+      *name = "y".to_string();
+    }
+  }
+}
+
+#[test]
+fn visitor_mut_only_overrides_what_it_needs() {
+  let mut node = parse("x^2 + f(x, |x|)");
+  node.accept_mut(&mut Rename);
+  assert_eq!(node, parse("y^2 + f(y, |y|)"));
+}
+
+/// Replaces operations on constants with their value
+struct ConstantFolding;
+
+impl Fold for ConstantFolding {
+  fn fold_binary(
+    &mut self,
+    op: Operation,
+    left: AstNode,
+    right: AstNode,
+    span: Span,
+  ) -> AstNode {
+    match (&left, &right) {
+      (
+        AstNode::Constant { value: a, .. },
+        AstNode::Constant { value: b, .. },
+      ) => match op.perform_binary(*a, *b) {
+        Ok(value) => AstNode::Constant { value, span },
+        Err(_) => self.rebuild(op, left, right, span),
+      },
+      _ => self.rebuild(op, left, right, span),
+    }
+  }
+}
+
+impl ConstantFolding {
+  fn rebuild(
+    &mut self,
+    op: Operation,
+    left: AstNode,
+    right: AstNode,
+    span: Span,
+  ) -> AstNode {
+    AstNode::Binary {
+      op,
+      left: Box::new(left),
+      right: Box::new(right),
+      span,
+    }
+  }
+}
+
+#[test]
+fn fold_rewrites_bottom_up() {
+  let folded = parse("x * (2 * 3 + 4)").fold(&mut ConstantFolding);
+  assert_eq!(folded, parse("x * 10"));
+  let folded = parse("1/0 + 2 * 2").fold(&mut ConstantFolding);
+  assert_eq!(folded, parse("1/0 + 4"));
+}
+
+#[test]
+fn evaluate_still_reports_spans() {
+  let ctx = Context::default();
+  let err = parse("1 + 2 * nope").evaluate(&ctx).unwrap_err();
+  assert_eq!(err.span(), Span::new(8, 12));
+}